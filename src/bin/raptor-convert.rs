@@ -0,0 +1,171 @@
+extern crate clap;
+#[macro_use]
+extern crate failure;
+extern crate raptor_rs;
+
+use clap::App;
+use clap::Arg;
+
+use failure::Error;
+
+use std::io;
+use std::io::BufWriter;
+use std::io::Read;
+use std::path::Path;
+
+use raptor_rs::compression;
+use raptor_rs::handler::report_errors;
+use raptor_rs::handler::FnHandler;
+use raptor_rs::handler::ParserHandlerExt;
+use raptor_rs::push::*;
+use raptor_rs::serializer::Serializer;
+use raptor_rs::serializer::SerializerHandler;
+use raptor_rs::Syntax;
+use raptor_rs::IRI;
+
+#[derive(Debug, Fail)]
+pub enum CommandError {
+    #[fail(display = "An argument that was expected is missing")]
+    MissingArgument,
+    #[fail(display = "Unknown syntax: {}", _0)]
+    UnknownSyntax(String),
+    #[fail(display = "Cannot tell the syntax of {}", _0)]
+    UnknownFileSyntax(String),
+    #[fail(display = "Prefixes must be given as prefix=IRI, not {}", _0)]
+    BadPrefix(String),
+    #[fail(display = "A base URI is required when reading from stdin")]
+    MissingBase,
+    #[fail(display = "Parsing reported {} errors", _0)]
+    ParseErrors(usize),
+    #[fail(display = "{}", _0)]
+    Serializer(String),
+}
+
+fn syntax(name: &str) -> Result<Syntax, CommandError> {
+    Syntax::from_name(name).ok_or_else(|| CommandError::UnknownSyntax(name.to_string()))
+}
+
+// An empty prefix declares the default namespace.
+fn prefix(declaration: &str) -> Result<(Option<&str>, IRI), CommandError> {
+    match declaration.find('=') {
+        Some(i) if i + 1 < declaration.len() => {
+            let prefix = &declaration[..i];
+            let iri = IRI::new(&declaration[i + 1..]);
+            Ok((if prefix.is_empty() { None } else { Some(prefix) }, iri))
+        }
+        _ => Err(CommandError::BadPrefix(declaration.to_string())),
+    }
+}
+
+fn main() -> Result<(), Error> {
+    let matches = App::new("raptor-convert")
+        .version("0.1")
+        .about("Convert RDF between syntaxes, keeping its prefixes")
+        .author("Phillip Lord")
+        .arg(
+            Arg::with_name("INPUT")
+                .help("Sets the input file to use, or - for stdin")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("input")
+                .short("i")
+                .long("input")
+                .takes_value(true)
+                .help("The syntax of the input; by default guessed from the file extension"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .takes_value(true)
+                .help("The syntax of the output; by default Turtle"),
+        )
+        .arg(
+            Arg::with_name("pretty")
+                .long("pretty")
+                .conflicts_with_all(&["output", "abbrev"])
+                .help("Write pretty Turtle; the same as -o turtle"),
+        )
+        .arg(
+            Arg::with_name("abbrev")
+                .long("abbrev")
+                .conflicts_with("output")
+                .help("Write abbreviated RDF/XML; the same as -o rdfxml-abbrev"),
+        )
+        .arg(
+            Arg::with_name("prefix")
+                .short("p")
+                .long("prefix")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("Declare an extra prefix in the output, as prefix=IRI"),
+        )
+        .arg(
+            Arg::with_name("base")
+                .long("base")
+                .takes_value(true)
+                .help("The base URI of the input; defaults to the file URI of INPUT"),
+        )
+        .get_matches();
+
+    let input = matches
+        .value_of("INPUT")
+        .ok_or(CommandError::MissingArgument)?;
+    let input_syntax = match matches.value_of("input") {
+        Some(name) => syntax(name)?,
+        None => Syntax::from_file_name(input)
+            .ok_or_else(|| CommandError::UnknownFileSyntax(input.to_string()))?,
+    };
+    let output_syntax = if matches.is_present("abbrev") {
+        Syntax::RdfXmlAbbrev
+    } else if matches.is_present("pretty") {
+        Syntax::Turtle
+    } else {
+        syntax(matches.value_of("output").unwrap_or("turtle"))?
+    };
+
+    let base = match matches.value_of("base") {
+        Some(base) => base.to_string(),
+        None if input == "-" => return Err(CommandError::MissingBase.into()),
+        None => IRI::from_path(input)?.to_string(),
+    };
+
+    let reader: Box<Read> = if input == "-" {
+        compression::decompress(io::stdin())?
+    } else {
+        compression::open(Path::new(input))?
+    };
+
+    let stdout = io::stdout();
+    let mut serializer = Serializer::to_writer(
+        output_syntax.name(),
+        Some(&base),
+        BufWriter::new(stdout.lock()),
+    )
+    .map_err(CommandError::Serializer)?;
+    for declaration in matches.values_of("prefix").into_iter().flat_map(|v| v) {
+        let (prefix, iri) = prefix(declaration)?;
+        if let Err(e) = serializer.set_namespace(prefix, &iri) {
+            eprintln!("{}", e);
+        }
+    }
+
+    let mut errors = 0;
+    {
+        let printer = FnHandler::new().on_error(report_errors(None, &mut errors));
+        let h = SerializerHandler::new(&mut serializer).tee(printer);
+
+        let mut p = Parser::new(input_syntax.name(), &base, &h);
+        p.parse_read(reader)?;
+    }
+
+    serializer.close()?;
+
+    if errors > 0 {
+        return Err(CommandError::ParseErrors(errors).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,304 @@
+extern crate clap;
+#[macro_use]
+extern crate failure;
+extern crate raptor_rs;
+
+use clap::App;
+use clap::Arg;
+
+use failure::Error;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::process;
+
+use raptor_rs::graph::Diff;
+use raptor_rs::graph::Graph;
+use raptor_rs::handler::report_errors;
+use raptor_rs::handler::FnHandler;
+use raptor_rs::namespaces::Namespaces;
+use raptor_rs::patch::Patch;
+use raptor_rs::push::*;
+use raptor_rs::Statement;
+use raptor_rs::Syntax;
+use raptor_rs::Term;
+use raptor_rs::IRI;
+
+#[derive(Debug, Fail)]
+pub enum CommandError {
+    #[fail(display = "An argument that was expected is missing")]
+    MissingArgument,
+    #[fail(display = "Unknown syntax: {}", _0)]
+    UnknownSyntax(String),
+    #[fail(display = "Cannot tell the syntax of {}", _0)]
+    UnknownFileSyntax(String),
+    #[fail(display = "{}: parsing reported {} errors", _0, _1)]
+    ParseErrors(String, usize),
+}
+
+// Blank nodes are given labels of their own to each file, as both
+// parses will have made up labels such as `genid1`, which would join
+// unrelated nodes when the differences are applied to OLD.
+fn scope(term: &Term, file: usize) -> Term {
+    match *term {
+        Term::Blank(ref label) => Term::Blank(format!("f{}_{}", file, label)),
+        ref other => other.clone(),
+    }
+}
+
+// Prefixes declared by the file are added to `namespaces`. `file` is 0
+// for OLD and 1 for NEW.
+fn read_graph(
+    file: usize,
+    path: &Path,
+    syntax: Option<Syntax>,
+    base: &str,
+    namespaces: &mut Namespaces,
+) -> Result<Graph, Error> {
+    let syntax = match syntax {
+        Some(syntax) => syntax,
+        None => path
+            .to_str()
+            .and_then(Syntax::from_file_name)
+            .ok_or_else(|| CommandError::UnknownFileSyntax(path.display().to_string()))?,
+    };
+    let name = path.display().to_string();
+    let mut graph = Graph::new();
+    let mut errors = 0;
+    {
+        let h = FnHandler::new()
+            .on_statement(|statement| {
+                graph.insert(Statement::new(
+                    scope(statement.subject(), file),
+                    statement.predicate().clone(),
+                    scope(statement.object(), file),
+                    statement.graph().map(|g| scope(g, file)),
+                ));
+                Ok(())
+            })
+            .on_error(report_errors(Some(&name), &mut errors))
+            .on_namespace(|namespace| {
+                namespaces.declare(&namespace);
+                Ok(())
+            });
+        let mut p = Parser::new(syntax.name(), base, &h);
+        p.parse_file(path)?;
+    }
+
+    if errors > 0 {
+        return Err(CommandError::ParseErrors(path.display().to_string(), errors).into());
+    }
+    Ok(graph)
+}
+
+// Both files are parsed against the same base, by default the file URI
+// of `old`, so that a relative IRI means the same in each.
+fn read_graphs(
+    old: &Path,
+    new: &Path,
+    syntax: Option<Syntax>,
+    base: Option<&str>,
+    namespaces: &mut Namespaces,
+) -> Result<(Graph, Graph), Error> {
+    let base = match base {
+        Some(base) => base.to_string(),
+        None => IRI::from_path(old)?.to_string(),
+    };
+    let a = read_graph(0, old, syntax, &base, namespaces)?;
+    let b = read_graph(1, new, syntax, &base, namespaces)?;
+    Ok((a, b))
+}
+
+fn write_ntriples(out: &mut Write, diff: &Diff) -> io::Result<()> {
+    for statement in diff.removed().iter() {
+        writeln!(out, "- {}", statement)?;
+    }
+    for statement in diff.added().iter() {
+        writeln!(out, "+ {}", statement)?;
+    }
+    Ok(())
+}
+
+fn write_human(
+    out: &mut Write,
+    old: &str,
+    new: &str,
+    diff: &Diff,
+    namespaces: &Namespaces,
+) -> io::Result<()> {
+    writeln!(out, "--- {}", old)?;
+    writeln!(out, "+++ {}", new)?;
+
+    // Group the changes by subject, removals first.
+    let mut subjects: BTreeMap<&Term, Vec<(char, &Statement)>> = BTreeMap::new();
+    for statement in diff.removed().iter() {
+        subjects
+            .entry(statement.subject())
+            .or_insert_with(Vec::new)
+            .push(('-', statement));
+    }
+    for statement in diff.added().iter() {
+        subjects
+            .entry(statement.subject())
+            .or_insert_with(Vec::new)
+            .push(('+', statement));
+    }
+
+    for (subject, changes) in subjects {
+        writeln!(out, "{}", namespaces.term(subject))?;
+        for (sign, statement) in changes {
+            write!(
+                out,
+                "  {} {} {}",
+                sign,
+                namespaces.term(statement.predicate()),
+                namespaces.term(statement.object())
+            )?;
+            if let Some(graph) = statement.graph() {
+                write!(out, " {}", namespaces.term(graph))?;
+            }
+            writeln!(out)?;
+        }
+    }
+
+    writeln!(
+        out,
+        "{} removed, {} added",
+        diff.removed().len(),
+        diff.added().len()
+    )
+}
+
+fn main() -> Result<(), Error> {
+    let matches = App::new("raptor-diff")
+        .version("0.1")
+        .about("Report the statements added and removed between two RDF files")
+        .author("Phillip Lord")
+        .arg(
+            Arg::with_name("OLD")
+                .help("The original file")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("NEW")
+                .help("The changed file")
+                .required(true)
+                .index(2),
+        )
+        .arg(
+            Arg::with_name("input")
+                .short("i")
+                .long("input")
+                .takes_value(true)
+                .help("The syntax of both files; by default guessed from their extensions"),
+        )
+        .arg(
+            Arg::with_name("base")
+                .long("base")
+                .takes_value(true)
+                .help("The base URI of both files; by default the file URI of OLD"),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .takes_value(true)
+                .possible_values(&["human", "ntriples", "rdfpatch"])
+                .default_value("human")
+                .help("The format of the differences"),
+        )
+        .get_matches();
+
+    let old = matches.value_of("OLD").ok_or(CommandError::MissingArgument)?;
+    let new = matches.value_of("NEW").ok_or(CommandError::MissingArgument)?;
+    let syntax = match matches.value_of("input") {
+        Some(name) => Some(
+            Syntax::from_name(name).ok_or_else(|| CommandError::UnknownSyntax(name.to_string()))?,
+        ),
+        None => None,
+    };
+    let base = matches.value_of("base");
+
+    // As diff: 0 if the same, 1 if different, 2 if there was trouble.
+    let mut namespaces = Namespaces::well_known();
+    let graphs = read_graphs(Path::new(old), Path::new(new), syntax, base, &mut namespaces);
+    let (a, b) = match graphs {
+        Ok(graphs) => graphs,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    };
+
+    let diff = a.diff(&b);
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    match matches.value_of("format") {
+        Some("ntriples") => write_ntriples(&mut out, &diff)?,
+        Some("rdfpatch") => write!(out, "{}", Patch::from_diff(&diff))?,
+        _ => write_human(&mut out, old, new, &diff, &namespaces)?,
+    }
+    out.flush()?;
+
+    if !diff.is_empty() {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Copies of a file under different names, with IRIs relative to
+    // wherever the file is.
+    #[test]
+    fn relative_iris() {
+        let dir = std::env::temp_dir().join(format!("raptor-diff-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let content = "<#s> <#p> <#o> .\n";
+        let old = dir.join("old.ttl");
+        let new = dir.join("new.ttl");
+        fs::write(&old, content).unwrap();
+        fs::write(&new, content).unwrap();
+
+        let mut namespaces = Namespaces::new();
+        let graphs = read_graphs(&old, &new, None, None, &mut namespaces);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let (a, b) = graphs.unwrap();
+        assert_eq!(1, a.len());
+        assert!(a.diff(&b).is_empty());
+    }
+
+    // Both files use _:genid1, for different nodes.
+    #[test]
+    fn apply_patch() {
+        let dir = std::env::temp_dir().join(format!("raptor-diff-patch-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let old = dir.join("old.nt");
+        let new = dir.join("new.nt");
+        fs::write(&old, "_:genid1 <http://www.example.com/p> \"a\" .\n").unwrap();
+        fs::write(
+            &new,
+            "_:genid2 <http://www.example.com/p> \"a\" .\n\
+             _:genid1 <http://www.example.com/p> \"b\" .\n",
+        )
+        .unwrap();
+
+        let mut namespaces = Namespaces::new();
+        let graphs = read_graphs(&old, &new, None, None, &mut namespaces);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let (a, b) = graphs.unwrap();
+        let patch = Patch::parse(&Patch::from_diff(&a.diff(&b)).to_string()).unwrap();
+        let mut patched = a.clone();
+        patch.apply(&mut patched).unwrap();
+        assert!(patched.is_isomorphic(&b));
+    }
+}
@@ -1,49 +1,265 @@
 extern crate clap;
 #[macro_use]
 extern crate failure;
+extern crate glob;
 extern crate raptor_rs;
+#[macro_use]
+extern crate serde_json;
 
 use clap::App;
 use clap::Arg;
 
 use failure::Error;
 
+use glob::Pattern;
+
 use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
 
+use raptor_rs::handler::FnHandler;
+use raptor_rs::handler::ParserHandlerExt;
+use raptor_rs::lint::Diagnostic;
 use raptor_rs::push::*;
+use raptor_rs::stats::StatsHandler;
+use raptor_rs::LogLevel;
+use raptor_rs::Syntax;
+use raptor_rs::IRI;
 
 #[derive(Debug, Fail)]
 pub enum CommandError {
     #[fail(display = "An argument that was expected is missing")]
     MissingArgument,
+    #[fail(display = "Unknown syntax: {}", _0)]
+    UnknownSyntax(String),
+    #[fail(display = "Cannot tell the syntax of {}", _0)]
+    UnknownFileSyntax(String),
+}
+
+fn stats_json(s: &StatsHandler) -> serde_json::Value {
+    let datatypes: serde_json::Map<String, serde_json::Value> = s
+        .datatypes()
+        .iter()
+        .map(|(datatype, count)| (datatype.to_string(), json!(count)))
+        .collect();
+    let messages: serde_json::Map<String, serde_json::Value> = s
+        .messages()
+        .iter()
+        .map(|(level, count)| (level.to_string(), json!(count)))
+        .collect();
+
+    json!({
+        "triples": s.triples(),
+        "distinct_subjects": s.distinct_subjects(),
+        "distinct_predicates": s.distinct_predicates(),
+        "distinct_objects": s.distinct_objects(),
+        "blank_nodes": s.blank_nodes(),
+        "graphs": s.graphs(),
+        "datatypes": datatypes,
+        "languages": s.languages(),
+        "messages": messages,
+    })
+}
+
+// Files named on the command line are always dumped; files found in
+// directories only if they match the filter, or otherwise if they look
+// like RDF.
+fn collect_files(
+    path: &Path,
+    filter: Option<&Pattern>,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), Error> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    entries.sort();
+
+    for entry in entries {
+        if fs::symlink_metadata(&entry)?.is_dir() {
+            collect_files(&entry, filter, files)?;
+            continue;
+        }
+        // Links to directories are not followed, so that a link back up
+        // the tree cannot send us round for ever.
+        if entry.is_dir() {
+            continue;
+        }
+
+        let name = entry
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        let wanted = match filter {
+            Some(pattern) => pattern.matches(name),
+            None => guess_syntax(&entry).is_some(),
+        };
+        if wanted {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
+
+fn guess_syntax(path: &Path) -> Option<Syntax> {
+    path.to_str().and_then(Syntax::from_file_name)
+}
+
+fn dump(path: &Path, syntax: Syntax) -> Result<StatsHandler, Error> {
+    let base = IRI::from_path(path)?.to_string();
+
+    let mut stats = StatsHandler::new();
+    {
+        let name = path.display().to_string();
+        let printer = FnHandler::new().on_error(|message| {
+            eprintln!("{}", Diagnostic::from_message(&name, &message));
+            Ok(())
+        });
+        let h = (&mut stats).tee(printer);
+        let mut p = Parser::new(syntax.name(), &base, &h);
+        p.parse_file(path)?;
+    }
+    Ok(stats)
 }
 
 fn main() -> Result<(), Error> {
-    //Hello
-    let matches = App::new("raptor-parse")
+    let matches = App::new("raptor-dump")
         .version("0.1")
-        .about("Parse an RDF file")
+        .about("Parse RDF files, reporting any problems")
         .author("Phillip Lord")
         .arg(
             Arg::with_name("INPUT")
-                .help("Sets the input file to use")
+                .help("The files, or directories of files, to parse")
                 .required(true)
-                .index(1),
+                .multiple(true),
+        )
+        .arg(
+            Arg::with_name("input")
+                .short("i")
+                .long("input")
+                .takes_value(true)
+                .help("The syntax of the input; by default guessed from the file extension"),
+        )
+        .arg(
+            Arg::with_name("glob")
+                .long("glob")
+                .takes_value(true)
+                .help("Only parse files in directories whose names match this pattern"),
+        )
+        .arg(
+            Arg::with_name("stats")
+                .long("stats")
+                .help("Print statistics about the parse"),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .takes_value(true)
+                .possible_values(&["text", "json"])
+                .default_value("text")
+                .help("The format of the summary or statistics"),
         )
         .get_matches();
 
-    let input = matches
-        .value_of("INPUT")
+    let inputs = matches
+        .values_of("INPUT")
         .ok_or(CommandError::MissingArgument)?;
+    let syntax = match matches.value_of("input") {
+        Some(name) => Some(
+            Syntax::from_name(name).ok_or_else(|| CommandError::UnknownSyntax(name.to_string()))?,
+        ),
+        None => None,
+    };
+    let filter = match matches.value_of("glob") {
+        Some(glob) => Some(Pattern::new(glob)?),
+        None => None,
+    };
+
+    let mut files = vec![];
+    for input in inputs {
+        let path = Path::new(input);
+        if path.is_dir() {
+            collect_files(path, filter.as_ref(), &mut files)?;
+        } else {
+            files.push(path.to_path_buf());
+        }
+    }
 
-    let m = MemoryParserHandler::new();
-    let mut p = Parser::new("rdfxml", "http://www.example.com", &m);
+    let json = matches.value_of("format") == Some("json");
+    let mut json_stats = serde_json::Map::new();
+    let mut total_errors = 0;
 
-    let st = fs::read_to_string(input)?;
+    for path in files {
+        let stats = match syntax.or_else(|| guess_syntax(&path)) {
+            Some(syntax) => dump(&path, syntax),
+            None => Err(CommandError::UnknownFileSyntax(path.display().to_string()).into()),
+        };
 
-    p.parse_chunk(&st);
-    p.parse_complete();
+        let stats = match stats {
+            Ok(stats) => stats,
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), e);
+                total_errors += 1;
+                continue;
+            }
+        };
 
-    dbg!(m.0);
+        let errors = stats.errors();
+        let warnings = stats.messages().get(&LogLevel::Warn).cloned().unwrap_or(0);
+        total_errors += errors;
+
+        if json && !matches.is_present("stats") {
+            json_stats.insert(
+                path.display().to_string(),
+                json!({
+                    "triples": stats.triples(),
+                    "errors": errors,
+                    "warnings": warnings,
+                }),
+            );
+        } else if !matches.is_present("stats") {
+            println!(
+                "{}: {} triples, {} errors, {} warnings",
+                path.display(),
+                stats.triples(),
+                errors,
+                warnings
+            );
+        } else if json {
+            json_stats.insert(path.display().to_string(), stats_json(&stats));
+        } else {
+            println!("==> {} <==", path.display());
+            print!("{}", stats);
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&json_stats)?);
+    }
+
+    if total_errors > 0 {
+        process::exit(1);
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_cycle() {
+        let dir = std::env::temp_dir().join(format!("raptor-dump-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.nt"), "").unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("loop")).unwrap();
+
+        let mut files = vec![];
+        let collected = collect_files(&dir, None, &mut files);
+        fs::remove_dir_all(&dir).unwrap();
+
+        collected.unwrap();
+        assert_eq!(vec![dir.join("a.nt")], files);
+    }
+}
@@ -0,0 +1,196 @@
+extern crate clap;
+#[macro_use]
+extern crate failure;
+extern crate raptor_rs;
+
+use clap::App;
+use clap::Arg;
+
+use failure::Error;
+
+use std::io;
+use std::io::BufWriter;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::process;
+
+use raptor_rs::compression;
+use raptor_rs::grep::*;
+use raptor_rs::handler::report_errors;
+use raptor_rs::handler::FnHandler;
+use raptor_rs::namespaces::Namespaces;
+use raptor_rs::push::*;
+use raptor_rs::Syntax;
+use raptor_rs::IRI;
+
+#[derive(Debug, Fail)]
+pub enum CommandError {
+    #[fail(display = "An argument that was expected is missing")]
+    MissingArgument,
+    #[fail(display = "Unknown syntax: {}", _0)]
+    UnknownSyntax(String),
+    #[fail(display = "Cannot tell the syntax of {}", _0)]
+    UnknownFileSyntax(String),
+    #[fail(display = "{}", _0)]
+    BadPattern(String),
+    #[fail(display = "Prefixes must be given as prefix=IRI, not {}", _0)]
+    BadPrefix(String),
+    #[fail(display = "A base URI is required when reading from stdin")]
+    MissingBase,
+}
+
+fn pattern(matches: &clap::ArgMatches, name: &str) -> Result<TermPattern, CommandError> {
+    TermPattern::parse(matches.value_of(name).unwrap_or("?")).map_err(CommandError::BadPattern)
+}
+
+fn main() -> Result<(), Error> {
+    let matches = App::new("raptor-grep")
+        .version("0.1")
+        .about("Print the statements in an RDF file which match a pattern")
+        .author("Phillip Lord")
+        .after_help(
+            "Patterns may be <IRI>, a CURIE such as dc:title, /regex/ to match \
+             literal values, @lang to match language tags, or ? for anything.",
+        )
+        .arg(
+            Arg::with_name("INPUT")
+                .help("Sets the input file to use, or - for stdin")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("subject")
+                .short("s")
+                .long("subject")
+                .takes_value(true)
+                .help("The pattern for subjects"),
+        )
+        .arg(
+            Arg::with_name("predicate")
+                .short("p")
+                .long("predicate")
+                .takes_value(true)
+                .help("The pattern for predicates"),
+        )
+        .arg(
+            Arg::with_name("object")
+                .short("o")
+                .long("object")
+                .takes_value(true)
+                .help("The pattern for objects"),
+        )
+        .arg(
+            Arg::with_name("prefix")
+                .long("prefix")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help(
+                    "Declare a prefix for CURIEs in patterns, as prefix=IRI; \
+                     common prefixes such as rdfs: are already declared",
+                ),
+        )
+        .arg(
+            Arg::with_name("count")
+                .short("c")
+                .long("count")
+                .help("Print only the number of matching statements"),
+        )
+        .arg(
+            Arg::with_name("input")
+                .short("i")
+                .long("input")
+                .takes_value(true)
+                .help("The syntax of the input; by default guessed from the file extension"),
+        )
+        .arg(
+            Arg::with_name("base")
+                .long("base")
+                .takes_value(true)
+                .help("The base URI of the input; defaults to the file URI of INPUT"),
+        )
+        .get_matches();
+
+    let input = matches
+        .value_of("INPUT")
+        .ok_or(CommandError::MissingArgument)?;
+    let syntax = match matches.value_of("input") {
+        Some(name) => {
+            Syntax::from_name(name).ok_or_else(|| CommandError::UnknownSyntax(name.to_string()))?
+        }
+        None => Syntax::from_file_name(input)
+            .ok_or_else(|| CommandError::UnknownFileSyntax(input.to_string()))?,
+    };
+
+    let mut triple = TriplePattern::new(
+        pattern(&matches, "subject")?,
+        pattern(&matches, "predicate")?,
+        pattern(&matches, "object")?,
+    );
+    for (prefix, iri) in Namespaces::well_known().iter() {
+        triple = triple.prefix(prefix, iri.clone());
+    }
+    for declaration in matches.values_of("prefix").into_iter().flat_map(|v| v) {
+        let i = declaration
+            .find('=')
+            .ok_or_else(|| CommandError::BadPrefix(declaration.to_string()))?;
+        triple = triple.prefix(&declaration[..i], IRI::new(&declaration[i + 1..]));
+    }
+
+    let base = match matches.value_of("base") {
+        Some(base) => base.to_string(),
+        None if input == "-" => return Err(CommandError::MissingBase.into()),
+        None => IRI::from_path(input)?.to_string(),
+    };
+
+    let reader: Box<Read> = if input == "-" {
+        compression::decompress(io::stdin())?
+    } else {
+        compression::open(Path::new(input))?
+    };
+
+    let count_only = matches.is_present("count");
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let mut count = 0;
+    let mut errors = 0;
+    let mut broken_pipe = false;
+    {
+        let printer = FnHandler::new()
+            .on_statement(|statement| {
+                count += 1;
+                if !count_only {
+                    // The reader has gone away, as with `| head`; there
+                    // is no point parsing any further.
+                    if let Err(e) = writeln!(out, "{}", statement) {
+                        broken_pipe = true;
+                        return Err(e.to_string());
+                    }
+                }
+                Ok(())
+            })
+            .on_error(report_errors(None, &mut errors));
+        let h = Grep::new(triple, printer);
+
+        let mut p = Parser::new(syntax.name(), &base, &h);
+        p.parse_read(reader)?;
+    }
+
+    if broken_pipe {
+        return Ok(());
+    }
+    if count_only {
+        writeln!(out, "{}", count)?;
+    }
+    out.flush()?;
+
+    // As grep: 0 if anything matched, 1 if nothing did, 2 on errors.
+    if errors > 0 {
+        process::exit(2);
+    }
+    if count == 0 {
+        process::exit(1);
+    }
+    Ok(())
+}
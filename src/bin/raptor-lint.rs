@@ -0,0 +1,97 @@
+extern crate clap;
+#[macro_use]
+extern crate failure;
+extern crate raptor_rs;
+extern crate serde_json;
+
+use clap::App;
+use clap::Arg;
+
+use failure::Error;
+
+use std::path::Path;
+use std::process;
+
+use raptor_rs::lint::*;
+use raptor_rs::Syntax;
+
+#[derive(Debug, Fail)]
+pub enum CommandError {
+    #[fail(display = "An argument that was expected is missing")]
+    MissingArgument,
+    #[fail(display = "Unknown syntax: {}", _0)]
+    UnknownSyntax(String),
+}
+
+fn main() -> Result<(), Error> {
+    let matches = App::new("raptor-lint")
+        .version("0.1")
+        .about("Check RDF files for errors and warnings")
+        .author("Phillip Lord")
+        .arg(
+            Arg::with_name("INPUT")
+                .help("The files to check")
+                .required(true)
+                .multiple(true),
+        )
+        .arg(
+            Arg::with_name("input")
+                .short("i")
+                .long("input")
+                .takes_value(true)
+                .help("The syntax of the input; by default guessed from the file extension"),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .takes_value(true)
+                .possible_values(&["human", "json", "sarif"])
+                .default_value("human")
+                .help("The format of the diagnostics"),
+        )
+        .get_matches();
+
+    let inputs = matches
+        .values_of("INPUT")
+        .ok_or(CommandError::MissingArgument)?;
+    let syntax = match matches.value_of("input") {
+        Some(name) => Some(
+            Syntax::from_name(name).ok_or_else(|| CommandError::UnknownSyntax(name.to_string()))?,
+        ),
+        None => None,
+    };
+
+    let mut diagnostics = vec![];
+    for input in inputs {
+        let path = Path::new(input);
+        // Problems with one file are reported with the rest, rather than
+        // ending the run.
+        let file_syntax = match syntax.or_else(|| Syntax::from_file_name(input)) {
+            Some(file_syntax) => file_syntax,
+            None => {
+                let message = format!("Cannot tell the syntax of {}", input);
+                diagnostics.push(Diagnostic::error(input, &message));
+                continue;
+            }
+        };
+        match lint_file(path, file_syntax) {
+            Ok(found) => diagnostics.extend(found),
+            Err(e) => diagnostics.push(Diagnostic::error(input, &e.to_string())),
+        }
+    }
+
+    match matches.value_of("format") {
+        Some("json") => println!("{}", serde_json::to_string_pretty(&to_json(&diagnostics))?),
+        Some("sarif") => println!("{}", serde_json::to_string_pretty(&to_sarif(&diagnostics))?),
+        _ => {
+            for diagnostic in &diagnostics {
+                println!("{}", diagnostic);
+            }
+        }
+    }
+
+    if diagnostics.iter().any(|d| d.is_error()) {
+        process::exit(1);
+    }
+    Ok(())
+}
@@ -8,42 +8,131 @@ use clap::Arg;
 
 use failure::Error;
 
-use std::fs;
+use std::io;
+use std::io::BufWriter;
+use std::io::Read;
+use std::path::Path;
 
+use raptor_rs::compression;
+use raptor_rs::handler::report_errors;
+use raptor_rs::handler::FnHandler;
 use raptor_rs::push::*;
+use raptor_rs::serializer::Serializer;
+use raptor_rs::Syntax;
+use raptor_rs::IRI;
 
 #[derive(Debug, Fail)]
 pub enum CommandError {
     #[fail(display = "An argument that was expected is missing")]
     MissingArgument,
+    #[fail(display = "Unknown syntax: {}", _0)]
+    UnknownSyntax(String),
+    #[fail(display = "A base URI is required when reading from stdin")]
+    MissingBase,
+    #[fail(display = "Parsing reported {} errors", _0)]
+    ParseErrors(usize),
+    #[fail(display = "{} can be parsed but not written", _0)]
+    ParseOnly(String),
+    #[fail(display = "{}", _0)]
+    Serializer(String),
+}
+
+fn syntax(name: &str) -> Result<Syntax, CommandError> {
+    Syntax::from_name(name).ok_or_else(|| CommandError::UnknownSyntax(name.to_string()))
+}
+
+// Some syntaxes, such as rdfa and guess, have only a parser.
+fn output_syntax(name: &str) -> Result<Syntax, CommandError> {
+    let syntax = syntax(name)?;
+    if Serializer::descriptions()
+        .iter()
+        .any(|d| d.syntax() == Some(syntax))
+    {
+        Ok(syntax)
+    } else {
+        Err(CommandError::ParseOnly(name.to_string()))
+    }
 }
 
 fn main() -> Result<(), Error> {
-    //Hello
     let matches = App::new("raptor-parse")
         .version("0.1")
-        .about("Parse an RDF file")
+        .about("Parse an RDF file, and write it out again")
         .author("Phillip Lord")
         .arg(
             Arg::with_name("INPUT")
-                .help("Sets the input file to use")
+                .help("Sets the input file to use, or - for stdin")
                 .required(true)
                 .index(1),
         )
+        .arg(
+            Arg::with_name("input")
+                .short("i")
+                .long("input")
+                .takes_value(true)
+                .default_value("rdfxml")
+                .help("The syntax of the input"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .takes_value(true)
+                .default_value("ntriples")
+                .help("The syntax of the output"),
+        )
+        .arg(
+            Arg::with_name("base")
+                .long("base")
+                .takes_value(true)
+                .help("The base URI of the input; defaults to the file URI of INPUT"),
+        )
         .get_matches();
 
     let input = matches
         .value_of("INPUT")
         .ok_or(CommandError::MissingArgument)?;
+    let input_syntax = syntax(matches.value_of("input").unwrap())?;
+    let output_syntax = output_syntax(matches.value_of("output").unwrap())?;
+
+    let base = match matches.value_of("base") {
+        Some(base) => base.to_string(),
+        None if input == "-" => return Err(CommandError::MissingBase.into()),
+        None => IRI::from_path(input)?.to_string(),
+    };
+
+    let reader: Box<Read> = if input == "-" {
+        compression::decompress(io::stdin())?
+    } else {
+        compression::open(Path::new(input))?
+    };
 
-    let m = MemoryParserHandler::new();
-    let mut p = Parser::new("rdfxml", "http://www.example.com", &m);
+    let stdout = io::stdout();
+    let mut serializer = Serializer::to_writer(
+        output_syntax.name(),
+        Some(&base),
+        BufWriter::new(stdout.lock()),
+    )
+    .map_err(CommandError::Serializer)?;
+    let mut errors = 0;
+    {
+        let h = FnHandler::new()
+            .on_statement(|statement| {
+                if let Err(e) = serializer.serialize_statement(&statement) {
+                    eprintln!("{}", e);
+                }
+                Ok(())
+            })
+            .on_error(report_errors(None, &mut errors));
 
-    let st = fs::read_to_string(input)?;
+        let mut p = Parser::new(input_syntax.name(), &base, &h);
+        p.parse_read(reader)?;
+    }
 
-    p.parse_chunk(&st);
-    p.parse_complete();
+    serializer.close()?;
 
-    println!("Parsed {} events", m.0.len());
+    if errors > 0 {
+        return Err(CommandError::ParseErrors(errors).into());
+    }
     Ok(())
 }
@@ -0,0 +1,423 @@
+extern crate clap;
+#[macro_use]
+extern crate failure;
+extern crate raptor_rs;
+
+use clap::App;
+use clap::Arg;
+
+use failure::Error;
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use raptor_rs::conneg;
+use raptor_rs::graph::Graph;
+use raptor_rs::handler::FnHandler;
+use raptor_rs::namespaces::Namespaces;
+use raptor_rs::push::*;
+use raptor_rs::serializer::Serializer;
+use raptor_rs::serializer::SerializerOptions;
+use raptor_rs::Statement;
+use raptor_rs::Syntax;
+use raptor_rs::SyntaxDescription;
+use raptor_rs::Term;
+use raptor_rs::IRI;
+
+#[derive(Debug, Fail)]
+pub enum CommandError {
+    #[fail(display = "An argument that was expected is missing")]
+    MissingArgument,
+    #[fail(display = "Cannot tell the syntax of {}", _0)]
+    UnknownFileSyntax(String),
+    #[fail(display = "Not a port number: {}", _0)]
+    BadPort(String),
+}
+
+// The syntaxes offered to clients, most preferred first.
+const SERVED: &[Syntax] = &[Syntax::Turtle, Syntax::NTriples, Syntax::RdfXml, Syntax::Json];
+
+struct Store {
+    graph: Graph,
+    namespaces: Namespaces,
+    base: Option<String>,
+    serializers: Vec<SyntaxDescription>,
+}
+
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    entries.sort();
+
+    for entry in entries {
+        if entry.is_dir() {
+            collect_files(&entry, files)?;
+        } else if guess_syntax(&entry).is_some() {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
+
+fn guess_syntax(path: &Path) -> Option<Syntax> {
+    path.to_str().and_then(Syntax::from_file_name)
+}
+
+// Blank node labels belong to their file, so each file's are given
+// their own prefix; otherwise `_:b1` in two files would be one node.
+fn scope(term: &Term, file: usize) -> Term {
+    match *term {
+        Term::Blank(ref label) => Term::Blank(format!("f{}_{}", file, label)),
+        ref other => other.clone(),
+    }
+}
+
+// `file` is the index of the file among those loaded.
+fn load(
+    file: usize,
+    path: &Path,
+    graph: &mut Graph,
+    namespaces: &mut Namespaces,
+) -> Result<(), Error> {
+    let syntax = guess_syntax(path)
+        .ok_or_else(|| CommandError::UnknownFileSyntax(path.display().to_string()))?;
+    let base = IRI::from_path(path)?.to_string();
+
+    let h = FnHandler::new()
+        .on_statement(|statement| {
+            graph.insert(Statement::new(
+                scope(statement.subject(), file),
+                statement.predicate().clone(),
+                scope(statement.object(), file),
+                statement.graph().map(|g| scope(g, file)),
+            ));
+            Ok(())
+        })
+        .on_error(|message| {
+            eprintln!("{}: {}", path.display(), message);
+            Ok(())
+        })
+        .on_namespace(|namespace| {
+            namespaces.declare(&namespace);
+            Ok(())
+        });
+    let mut p = Parser::new(syntax.name(), &base, &h);
+    p.parse_file(path)?;
+    Ok(())
+}
+
+// The statements about `resource`, and about any blank nodes they
+// lead to, which would otherwise be left dangling.
+fn describe(graph: &Graph, resource: Term) -> Vec<&Statement> {
+    let mut description = vec![];
+    let mut seen = HashSet::new();
+    let mut pending = vec![resource];
+    while let Some(subject) = pending.pop() {
+        if !seen.insert(subject.clone()) {
+            continue;
+        }
+        for statement in graph.iter().filter(|s| s.subject() == &subject) {
+            if let Term::Blank(_) = *statement.object() {
+                pending.push(statement.object().clone());
+            }
+            description.push(statement);
+        }
+    }
+    description
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            // from_str_radix would also take a sign, as in %+1.
+            b'%' if i + 2 < bytes.len()
+                && bytes[i + 1].is_ascii_hexdigit()
+                && bytes[i + 2].is_ascii_hexdigit() =>
+            {
+                let hex = String::from_utf8_lossy(&bytes[i + 1..i + 3]);
+                decoded.push(u8::from_str_radix(&hex, 16).unwrap());
+                i += 3;
+                continue;
+            }
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// The IRI asked for, either as `?uri=` or as a path under the base.
+fn resource(store: &Store, target: &str) -> Option<String> {
+    let (path, query) = match target.find('?') {
+        Some(i) => (&target[..i], Some(&target[i + 1..])),
+        None => (target, None),
+    };
+    let uri = query.and_then(|query| {
+        query
+            .split('&')
+            .find(|p| p.starts_with("uri="))
+            .map(|p| percent_decode(&p[4..]))
+    });
+    match (uri, &store.base) {
+        (Some(uri), _) => Some(uri),
+        (None, &Some(ref base)) if path != "/" => {
+            Some(format!("{}{}", base, percent_decode(path.trim_start_matches('/'))))
+        }
+        _ => None,
+    }
+}
+
+fn respond(
+    stream: &mut TcpStream,
+    head: bool,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nVary: Accept\r\n\
+         Connection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    if !head {
+        stream.write_all(body.as_bytes())?;
+    }
+    stream.flush()
+}
+
+fn serve(store: &Store, mut stream: TcpStream) -> io::Result<()> {
+    // A client which connects and sends nothing is given up on.
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut accept = String::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        let mut header = line.splitn(2, ':');
+        if let (Some(name), Some(value)) = (header.next(), header.next()) {
+            if name.trim().eq_ignore_ascii_case("accept") {
+                accept = value.trim().to_string();
+            }
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let target = parts.next().unwrap_or("/");
+    eprintln!("{} {}", method, target);
+
+    let head = method == "HEAD";
+    if method != "GET" && !head {
+        return respond(&mut stream, false, "405 Method Not Allowed", "text/plain", "");
+    }
+
+    let iri = match resource(store, target) {
+        Some(iri) => iri,
+        None => {
+            // An index of everything which can be asked for.
+            let mut subjects: Vec<String> = store
+                .graph
+                .iter()
+                .filter_map(|s| match *s.subject() {
+                    Term::URI(ref iri) => Some(iri.to_string()),
+                    _ => None,
+                })
+                .collect();
+            subjects.dedup();
+            let mut index = subjects.join("\n");
+            index.push('\n');
+            return respond(&mut stream, head, "200 OK", "text/plain", &index);
+        }
+    };
+
+    let negotiated = match conneg::choose(&accept, &store.serializers) {
+        Some(negotiated) => negotiated,
+        None => {
+            let offered: Vec<&str> = SERVED.iter().map(|s| s.name()).collect();
+            let body = format!("Available syntaxes: {}\n", offered.join(", "));
+            return respond(&mut stream, head, "406 Not Acceptable", "text/plain", &body);
+        }
+    };
+
+    let description = describe(&store.graph, Term::URI(IRI::new(&iri)));
+    if description.is_empty() {
+        let body = format!("Nothing is known about {}\n", iri);
+        return respond(&mut stream, head, "404 Not Found", "text/plain", &body);
+    }
+
+    match serialize(store, negotiated.syntax(), &iri, &description) {
+        Ok(body) => respond(&mut stream, head, "200 OK", negotiated.content_type(), &body),
+        Err(e) => {
+            eprintln!("{}: {}", iri, e);
+            let body = format!("{}\n", e);
+            respond(&mut stream, head, "500 Internal Server Error", "text/plain", &body)
+        }
+    }
+}
+
+fn serialize(
+    store: &Store,
+    syntax: Syntax,
+    iri: &str,
+    description: &[&Statement],
+) -> Result<String, String> {
+    // Relative IRIs would be relative to the resource, which is more
+    // confusing than helpful.
+    let options = SerializerOptions::new().relative_uris(false);
+    let mut serializer = Serializer::with_options(syntax.name(), Some(iri), &options)?;
+    serializer.set_namespaces(&store.namespaces);
+    for statement in description {
+        serializer.serialize_statement(statement)?;
+    }
+    Ok(serializer.finish())
+}
+
+fn main() -> Result<(), Error> {
+    let matches = App::new("raptor-serve")
+        .version("0.1")
+        .about("Serve descriptions of the resources in a directory of RDF files over HTTP")
+        .author("Phillip Lord")
+        .after_help(
+            "Ask for a resource as /?uri=IRI, or, with --base, as a path under the base. \
+             The syntax is chosen from the Accept header: Turtle, N-Triples, RDF/XML or \
+             RDF/JSON. / alone lists the resources.",
+        )
+        .arg(
+            Arg::with_name("DIRECTORY")
+                .help("The directory of RDF files to serve")
+                .required(true)
+                .index(1),
+        )
+        .arg(
+            Arg::with_name("port")
+                .short("p")
+                .long("port")
+                .takes_value(true)
+                .default_value("8080")
+                .help("The port to listen on"),
+        )
+        .arg(
+            Arg::with_name("base")
+                .long("base")
+                .takes_value(true)
+                .help("The IRI which request paths are relative to"),
+        )
+        .get_matches();
+
+    let directory = matches
+        .value_of("DIRECTORY")
+        .ok_or(CommandError::MissingArgument)?;
+    let port = matches.value_of("port").unwrap_or("8080");
+    let port: u16 = port
+        .parse()
+        .map_err(|_| CommandError::BadPort(port.to_string()))?;
+
+    let mut files = vec![];
+    collect_files(Path::new(directory), &mut files)?;
+
+    let mut graph = Graph::new();
+    let mut namespaces = Namespaces::new();
+    for (i, file) in files.iter().enumerate() {
+        if let Err(e) = load(i, file, &mut graph, &mut namespaces) {
+            eprintln!("{}: {}", file.display(), e);
+        }
+    }
+
+    // In the order of SERVED, which decides ties such as */*.
+    let descriptions = Serializer::descriptions();
+    let store = Store {
+        graph,
+        namespaces,
+        base: matches.value_of("base").map(|b| b.to_string()),
+        serializers: SERVED
+            .iter()
+            .filter_map(|&syntax| {
+                descriptions
+                    .iter()
+                    .find(|d| d.syntax() == Some(syntax))
+                    .cloned()
+            })
+            .collect(),
+    };
+
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    eprintln!(
+        "Serving {} statements from {} files on http://localhost:{}/",
+        store.graph.len(),
+        files.len(),
+        port
+    );
+
+    // Each connection has its own thread, so that a slow client does
+    // not hold up the rest.
+    let store = Arc::new(store);
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let store = store.clone();
+                thread::spawn(move || {
+                    if let Err(e) = serve(&store, stream) {
+                        eprintln!("{}", e);
+                    }
+                });
+            }
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_decoding() {
+        assert_eq!("http://a/b c", percent_decode("http%3A%2F%2Fa/b%20c"));
+        assert_eq!("%+1%zz%4", percent_decode("%+1%zz%4"));
+    }
+
+    #[test]
+    fn blank_nodes_per_file() {
+        let dir = std::env::temp_dir().join(format!("raptor-serve-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let a = dir.join("a.nt");
+        let b = dir.join("b.nt");
+        fs::write(&a, "<http://example.com/a> <http://example.com/p> _:b1 .\n").unwrap();
+        fs::write(&b, "<http://example.com/b> <http://example.com/p> _:b1 .\n").unwrap();
+
+        let mut graph = Graph::new();
+        let mut namespaces = Namespaces::new();
+        load(0, &a, &mut graph, &mut namespaces).unwrap();
+        load(1, &b, &mut graph, &mut namespaces).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let objects: HashSet<&Term> = graph.iter().map(|s| s.object()).collect();
+        assert_eq!(2, objects.len());
+
+        let a = Term::URI(IRI::new("http://example.com/a"));
+        assert_eq!(1, describe(&graph, a).len());
+    }
+}
@@ -0,0 +1,532 @@
+//! RDF Dataset Canonicalization (RDFC-1.0).
+//!
+//! An implementation of https://www.w3.org/TR/rdf-canon/, which gives
+//! every blank node in a dataset a label that depends only on the
+//! structure of the dataset, so that isomorphic datasets produce
+//! identical canonical N-Quads.
+use super::*;
+
+use sha2::Digest;
+use sha2::Sha256;
+use sha2::Sha384;
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Sha384,
+}
+
+impl Default for HashAlgorithm {
+    fn default() -> HashAlgorithm {
+        HashAlgorithm::Sha256
+    }
+}
+
+impl HashAlgorithm {
+    fn hash(&self, data: &str) -> String {
+        match self {
+            HashAlgorithm::Sha256 => hex(&Sha256::digest(data.as_bytes())),
+            HashAlgorithm::Sha384 => hex(&Sha384::digest(data.as_bytes())),
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// The result of canonicalizing a dataset.
+#[derive(Clone, Debug)]
+pub struct Canonical {
+    statements: Vec<Statement>,
+    issued: BTreeMap<String, String>,
+}
+
+impl Canonical {
+    /// The relabelled statements, in canonical order.
+    pub fn statements(&self) -> &[Statement] {
+        &self.statements
+    }
+
+    /// The canonical label issued for each input blank node label.
+    pub fn issued(&self) -> &BTreeMap<String, String> {
+        &self.issued
+    }
+
+    /// The canonical N-Quads document.
+    pub fn to_nquads(&self) -> String {
+        self.statements
+            .iter()
+            .map(|statement| format!("{}\n", statement))
+            .collect()
+    }
+}
+
+/// Canonicalize a dataset, hashing with SHA-256.
+pub fn canonicalize<'a, I>(statements: I) -> Result<Canonical, String>
+where
+    I: IntoIterator<Item = &'a Statement>,
+{
+    Canonicalizer::new().canonicalize(statements)
+}
+
+#[derive(Clone, Debug)]
+struct IdentifierIssuer {
+    prefix: &'static str,
+    issued: Vec<String>,
+    map: HashMap<String, String>,
+}
+
+impl IdentifierIssuer {
+    fn new(prefix: &'static str) -> IdentifierIssuer {
+        IdentifierIssuer {
+            prefix,
+            issued: Vec::new(),
+            map: HashMap::new(),
+        }
+    }
+
+    fn get(&self, existing: &str) -> Option<&String> {
+        self.map.get(existing)
+    }
+
+    fn issue(&mut self, existing: &str) -> String {
+        if let Some(id) = self.map.get(existing) {
+            return id.clone();
+        }
+        let id = format!("{}{}", self.prefix, self.issued.len());
+        self.issued.push(existing.to_string());
+        self.map.insert(existing.to_string(), id.clone());
+        id
+    }
+}
+
+/// Canonicalizes datasets with a choice of hash algorithm.
+///
+/// Some datasets -- "poison" graphs with many indistinguishable blank
+/// nodes -- take exponential time to canonicalize, so the number of
+/// calls to the N-degree hashing step can be bounded.
+#[derive(Clone, Debug)]
+pub struct Canonicalizer {
+    algorithm: HashAlgorithm,
+    max_calls: Option<usize>,
+}
+
+impl Default for Canonicalizer {
+    fn default() -> Canonicalizer {
+        Canonicalizer {
+            algorithm: HashAlgorithm::default(),
+            max_calls: None,
+        }
+    }
+}
+
+impl Canonicalizer {
+    pub fn new() -> Canonicalizer {
+        Canonicalizer::default()
+    }
+
+    pub fn algorithm(mut self, algorithm: HashAlgorithm) -> Canonicalizer {
+        self.algorithm = algorithm;
+        self
+    }
+
+    pub fn max_calls(mut self, max_calls: usize) -> Canonicalizer {
+        self.max_calls = Some(max_calls);
+        self
+    }
+
+    pub fn canonicalize<'a, I>(&self, statements: I) -> Result<Canonical, String>
+    where
+        I: IntoIterator<Item = &'a Statement>,
+    {
+        // Datasets are sets, so duplicates play no part.
+        let statements: BTreeSet<&Statement> = statements.into_iter().collect();
+
+        let mut state = State {
+            algorithm: self.algorithm,
+            max_calls: self.max_calls,
+            calls: 0,
+            blank_node_to_quads: BTreeMap::new(),
+            canonical: IdentifierIssuer::new("c14n"),
+        };
+
+        for &statement in &statements {
+            // RDF datasets have no blank predicates, and the algorithm
+            // has no way to label them.
+            if let Term::Blank(_) = statement.predicate {
+                return Err(format!("Blank node predicate in {}", statement));
+            }
+            for label in blank_labels(statement) {
+                state
+                    .blank_node_to_quads
+                    .entry(label)
+                    .or_insert_with(Vec::new)
+                    .push(statement);
+            }
+        }
+
+        let mut hash_to_blank_nodes: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+        for label in state.blank_node_to_quads.keys() {
+            hash_to_blank_nodes
+                .entry(state.hash_first_degree(label))
+                .or_insert_with(Vec::new)
+                .push(*label);
+        }
+
+        // Blank nodes with a unique first degree hash are labelled in
+        // hash order; the rest need the N-degree hash to separate them.
+        let mut shared = vec![];
+        for (_, labels) in hash_to_blank_nodes {
+            if labels.len() == 1 {
+                state.canonical.issue(labels[0]);
+            } else {
+                shared.push(labels);
+            }
+        }
+
+        for labels in shared {
+            let mut hash_path_list = vec![];
+            for label in labels {
+                if state.canonical.get(label).is_some() {
+                    continue;
+                }
+                let mut issuer = IdentifierIssuer::new("b");
+                issuer.issue(label);
+                hash_path_list.push(state.hash_n_degree(label, issuer)?);
+            }
+
+            hash_path_list.sort_by(|a, b| a.0.cmp(&b.0));
+            for (_, issuer) in hash_path_list {
+                for existing in &issuer.issued {
+                    state.canonical.issue(existing);
+                }
+            }
+        }
+
+        let relabel = |term: &Term| match term {
+            Term::Blank(label) => Term::Blank(state.canonical.map[label].clone()),
+            other => other.clone(),
+        };
+
+        let mut canonical: Vec<Statement> = statements
+            .iter()
+            .map(|statement| Statement {
+                subject: relabel(&statement.subject),
+                predicate: relabel(&statement.predicate),
+                object: relabel(&statement.object),
+                graph: statement.graph.as_ref().map(|g| relabel(g)),
+            })
+            .collect();
+        // Canonical N-Quads are sorted by their serialization, which
+        // is not the same as our ordering of statements.
+        canonical.sort_by_cached_key(|statement| statement.to_string());
+
+        Ok(Canonical {
+            statements: canonical,
+            issued: state.canonical.map.clone().into_iter().collect(),
+        })
+    }
+}
+
+fn blank_labels(statement: &Statement) -> Vec<&str> {
+    let mut labels = vec![];
+    for term in &[
+        Some(&statement.subject),
+        Some(&statement.object),
+        statement.graph.as_ref(),
+    ] {
+        if let Some(Term::Blank(label)) = term {
+            if !labels.contains(&label.as_str()) {
+                labels.push(label.as_str());
+            }
+        }
+    }
+    labels
+}
+
+struct State<'a> {
+    algorithm: HashAlgorithm,
+    max_calls: Option<usize>,
+    calls: usize,
+    blank_node_to_quads: BTreeMap<&'a str, Vec<&'a Statement>>,
+    canonical: IdentifierIssuer,
+}
+
+impl<'a> State<'a> {
+    fn hash_first_degree(&self, reference: &str) -> String {
+        let blank = |term: &Term| match term {
+            Term::Blank(label) if label == reference => Term::Blank("a".to_string()),
+            Term::Blank(_) => Term::Blank("z".to_string()),
+            other => other.clone(),
+        };
+
+        let mut nquads: Vec<String> = self.blank_node_to_quads[reference]
+            .iter()
+            .map(|statement| {
+                let statement = Statement {
+                    subject: blank(&statement.subject),
+                    predicate: statement.predicate.clone(),
+                    object: blank(&statement.object),
+                    graph: statement.graph.as_ref().map(|g| blank(g)),
+                };
+                format!("{}\n", statement)
+            })
+            .collect();
+        nquads.sort();
+
+        self.algorithm.hash(&nquads.concat())
+    }
+
+    fn hash_related(
+        &self,
+        related: &str,
+        statement: &Statement,
+        issuer: &IdentifierIssuer,
+        position: &str,
+    ) -> String {
+        let mut input = position.to_string();
+        if position != "g" {
+            input.push_str(&format!("{}", statement.predicate));
+        }
+        match self.canonical.get(related).or_else(|| issuer.get(related)) {
+            Some(id) => input.push_str(&format!("_:{}", id)),
+            None => input.push_str(&self.hash_first_degree(related)),
+        }
+        self.algorithm.hash(&input)
+    }
+
+    fn hash_n_degree(
+        &mut self,
+        identifier: &str,
+        mut issuer: IdentifierIssuer,
+    ) -> Result<(String, IdentifierIssuer), String> {
+        self.calls += 1;
+        if let Some(max_calls) = self.max_calls {
+            if self.calls > max_calls {
+                return Err(format!(
+                    "Canonicalization exceeded {} N-degree hash calls",
+                    max_calls
+                ));
+            }
+        }
+
+        let mut hash_to_related: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for statement in &self.blank_node_to_quads[identifier] {
+            let positions = [
+                ("s", Some(&statement.subject)),
+                ("o", Some(&statement.object)),
+                ("g", statement.graph.as_ref()),
+            ];
+            for &(position, term) in &positions {
+                if let Some(Term::Blank(related)) = term {
+                    if related != identifier {
+                        hash_to_related
+                            .entry(self.hash_related(related, statement, &issuer, position))
+                            .or_insert_with(Vec::new)
+                            .push(related.clone());
+                    }
+                }
+            }
+        }
+
+        let mut data_to_hash = String::new();
+        for (related_hash, blank_nodes) in hash_to_related {
+            data_to_hash.push_str(&related_hash);
+
+            let mut chosen: Option<(String, IdentifierIssuer)> = None;
+            'permutations: for permutation in permutations(&blank_nodes) {
+                let mut issuer_copy = issuer.clone();
+                let mut path = String::new();
+                let mut recursion_list = vec![];
+
+                for related in &permutation {
+                    match self.canonical.get(related) {
+                        Some(id) => path.push_str(&format!("_:{}", id)),
+                        None => {
+                            if issuer_copy.get(related).is_none() {
+                                recursion_list.push(related.clone());
+                            }
+                            path.push_str(&format!("_:{}", issuer_copy.issue(related)));
+                        }
+                    }
+                    if worse(&path, &chosen) {
+                        continue 'permutations;
+                    }
+                }
+
+                for related in recursion_list {
+                    let (hash, result_issuer) =
+                        self.hash_n_degree(&related, issuer_copy.clone())?;
+                    path.push_str(&format!("_:{}", issuer_copy.issue(&related)));
+                    path.push_str(&format!("<{}>", hash));
+                    issuer_copy = result_issuer;
+                    if worse(&path, &chosen) {
+                        continue 'permutations;
+                    }
+                }
+
+                let better = match chosen {
+                    Some((ref chosen_path, _)) => path < *chosen_path,
+                    None => true,
+                };
+                if better {
+                    chosen = Some((path, issuer_copy));
+                }
+            }
+
+            if let Some((chosen_path, chosen_issuer)) = chosen {
+                data_to_hash.push_str(&chosen_path);
+                issuer = chosen_issuer;
+            }
+        }
+
+        Ok((self.algorithm.hash(&data_to_hash), issuer))
+    }
+}
+
+// A partial path can be abandoned once it is already longer than, and
+// sorts after, the best complete path so far.
+fn worse(path: &str, chosen: &Option<(String, IdentifierIssuer)>) -> bool {
+    match chosen {
+        Some((chosen_path, _)) => path.len() >= chosen_path.len() && path > chosen_path.as_str(),
+        None => false,
+    }
+}
+
+fn permutations(items: &[String]) -> Vec<Vec<String>> {
+    if items.len() <= 1 {
+        return vec![items.to_vec()];
+    }
+    let mut all = vec![];
+    for i in 0..items.len() {
+        let mut rest = items.to_vec();
+        let first = rest.remove(i);
+        for mut permutation in permutations(&rest) {
+            permutation.insert(0, first.clone());
+            all.push(permutation);
+        }
+    }
+    all
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graph::Graph;
+
+    fn iri(s: &str) -> Term {
+        Term::URI(IRI::new(s))
+    }
+
+    fn blank(s: &str) -> Term {
+        Term::Blank(s.to_string())
+    }
+
+    fn triple(s: Term, p: Term, o: Term) -> Statement {
+        Statement::new(s, p, o, None)
+    }
+
+    #[test]
+    fn no_blank_nodes() {
+        let statements = vec![triple(
+            iri("http://example.com/#p"),
+            iri("http://example.com/#q"),
+            Term::Literal(Literal::new("value", None, None)),
+        )];
+        let canonical = canonicalize(&statements).unwrap();
+
+        assert_eq!(
+            canonical.to_nquads(),
+            "<http://example.com/#p> <http://example.com/#q> \"value\" .\n"
+        );
+    }
+
+    // After test 002 of the W3C rdf-canon test suite.
+    #[test]
+    fn unique_hashes() {
+        let statements = vec![
+            triple(
+                iri("http://example.com/#p"),
+                iri("http://example.com/#q"),
+                blank("e0"),
+            ),
+            triple(
+                iri("http://example.com/#p"),
+                iri("http://example.com/#r"),
+                blank("e1"),
+            ),
+            triple(
+                blank("e0"),
+                iri("http://example.com/#s"),
+                iri("http://example.com/#u"),
+            ),
+            triple(
+                blank("e1"),
+                iri("http://example.com/#t"),
+                iri("http://example.com/#u"),
+            ),
+        ];
+        let canonical = canonicalize(&statements).unwrap();
+
+        assert_eq!(
+            canonical.to_nquads(),
+            "<http://example.com/#p> <http://example.com/#q> _:c14n0 .\n\
+             <http://example.com/#p> <http://example.com/#r> _:c14n1 .\n\
+             _:c14n0 <http://example.com/#s> <http://example.com/#u> .\n\
+             _:c14n1 <http://example.com/#t> <http://example.com/#u> .\n"
+        );
+    }
+
+    #[test]
+    fn isomorphic_datasets_agree() {
+        let cycle = |a: &str, b: &str, c: &str| {
+            vec![
+                triple(blank(a), iri("http://example.com/#p"), blank(b)),
+                triple(blank(b), iri("http://example.com/#p"), blank(c)),
+                triple(blank(c), iri("http://example.com/#p"), blank(a)),
+            ]
+        };
+        let first = cycle("x", "y", "z");
+        let second = cycle("n3", "n1", "n2");
+
+        for algorithm in &[HashAlgorithm::Sha256, HashAlgorithm::Sha384] {
+            let canonicalizer = Canonicalizer::new().algorithm(*algorithm);
+            let a = canonicalizer.canonicalize(&first).unwrap();
+            let b = canonicalizer.canonicalize(&second).unwrap();
+            assert_eq!(a.to_nquads(), b.to_nquads());
+
+            let graph: Graph = a.statements().iter().cloned().collect();
+            let original: Graph = first.iter().cloned().collect();
+            assert!(graph.is_isomorphic(&original));
+        }
+    }
+
+    #[test]
+    fn blank_predicate() {
+        let statements = vec![triple(
+            iri("http://example.com/#p"),
+            blank("q"),
+            iri("http://example.com/#u"),
+        )];
+
+        assert!(canonicalize(&statements).is_err());
+    }
+
+    #[test]
+    fn call_limit() {
+        let statements = vec![
+            triple(blank("a"), iri("http://example.com/#p"), blank("b")),
+            triple(blank("b"), iri("http://example.com/#p"), blank("a")),
+        ];
+
+        assert!(Canonicalizer::new()
+            .max_calls(0)
+            .canonicalize(&statements)
+            .is_err());
+    }
+}
@@ -0,0 +1,102 @@
+//! Handing parse events to other threads.
+//!
+//! A `push::Parser` has to stay on the thread that created it, but the
+//! statements it produces are plain data and are `Send`, so they can be
+//! processed elsewhere. `ChannelHandler` sends each event down a
+//! bounded channel; once the channel is full the parse blocks until
+//! the consumers catch up.
+use super::push::ParserHandler;
+use super::*;
+
+use std::sync::mpsc::sync_channel;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::SyncSender;
+
+pub type Event = Result<Statement, LogMessage>;
+
+#[derive(Debug)]
+pub struct ChannelHandler {
+    sender: SyncSender<Event>,
+}
+
+impl ChannelHandler {
+    /// A handler, and the receiver for its events, with room for
+    /// `bound` events in flight.
+    pub fn new(bound: usize) -> (ChannelHandler, Receiver<Event>) {
+        let (sender, receiver) = sync_channel(bound);
+        (ChannelHandler { sender }, receiver)
+    }
+
+    pub fn from_sender(sender: SyncSender<Event>) -> ChannelHandler {
+        ChannelHandler { sender }
+    }
+}
+
+impl ParserHandler for ChannelHandler {
+    fn handle_statement(&mut self, statement: Statement) -> Result<(), String> {
+        self.sender
+            .send(Ok(statement))
+            .map_err(|_| "Statement receiver has hung up".to_string())
+    }
+
+    fn handle_error(&mut self, error: LogMessage) -> Result<(), String> {
+        self.sender
+            .send(Err(error))
+            .map_err(|_| "Error receiver has hung up".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use push::Parser;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::thread;
+
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn model_is_send() {
+        assert_send::<Statement>();
+        assert_send::<Term>();
+        assert_send::<LogMessage>();
+        assert_send::<Namespace>();
+        assert_send::<Event>();
+    }
+
+    #[test]
+    fn worker_pool() {
+        let about = include_str!("./test-files/about_two.rdf");
+        let (h, receiver) = ChannelHandler::new(1);
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let workers: Vec<_> = (0..3)
+            .map(|_| {
+                let receiver = receiver.clone();
+                thread::spawn(move || {
+                    let mut count = 0;
+                    loop {
+                        let event = receiver.lock().unwrap().recv();
+                        match event {
+                            Ok(Ok(_)) => count += 1,
+                            Ok(Err(_)) => {}
+                            Err(_) => return count,
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        {
+            let mut p = Parser::new("rdfxml", "http://www.example.com", &h);
+            p.parse_chunk(about);
+            p.parse_complete();
+        }
+        // Hang up, so that the workers finish.
+        drop(h);
+
+        let total: usize = workers.into_iter().map(|w| w.join().unwrap()).sum();
+        assert_eq!(2, total);
+    }
+}
@@ -0,0 +1,199 @@
+//! Reading compressed documents, such as `.nt.gz` dumps, as they are
+//! parsed rather than decompressing them first.
+//!
+//! Compression is recognised by its magic bytes alone; a file's
+//! extension gives only its syntax, as in `syntax_extension`. Each
+//! format needs its feature: `gz`, `bz2` or `xz`. Without it,
+//! compressed input is an error rather than being handed to the parser
+//! as it is.
+use super::*;
+
+use std::fs::File;
+use std::io::Cursor;
+use std::io::Read;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Compression {
+    None,
+    Gzip,
+    Bzip2,
+    Xz,
+}
+
+// The longest magic number, which is xz's.
+const MAGIC_LEN: usize = 6;
+
+impl Compression {
+    pub fn from_extension(extension: &str) -> Compression {
+        match extension.to_lowercase().as_str() {
+            "gz" => Compression::Gzip,
+            "bz2" => Compression::Bzip2,
+            "xz" => Compression::Xz,
+            _ => Compression::None,
+        }
+    }
+
+    /// The compression of a document starting with `bytes`.
+    pub fn from_magic(bytes: &[u8]) -> Compression {
+        if bytes.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if bytes.starts_with(b"BZh") {
+            Compression::Bzip2
+        } else if bytes.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Compression::Xz
+        } else {
+            Compression::None
+        }
+    }
+
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip => Some("gz"),
+            Compression::Bzip2 => Some("bz2"),
+            Compression::Xz => Some("xz"),
+        }
+    }
+
+    /// Whether this build can read this compression.
+    pub fn is_supported(&self) -> bool {
+        match self {
+            Compression::None => true,
+            Compression::Gzip => cfg!(feature = "gz"),
+            Compression::Bzip2 => cfg!(feature = "bz2"),
+            Compression::Xz => cfg!(feature = "xz"),
+        }
+    }
+
+    // Wrap `reader` in a decoder for this compression.
+    fn decoder<'a, R: Read + 'a>(&self, reader: R) -> io::Result<Box<Read + 'a>> {
+        match self {
+            Compression::None => Ok(Box::new(reader)),
+            #[cfg(feature = "gz")]
+            Compression::Gzip => Ok(Box::new(::flate2::read::MultiGzDecoder::new(reader))),
+            #[cfg(feature = "bz2")]
+            Compression::Bzip2 => Ok(Box::new(::bzip2::read::MultiBzDecoder::new(reader))),
+            #[cfg(feature = "xz")]
+            Compression::Xz => Ok(Box::new(::xz2::read::XzDecoder::new_multi_decoder(reader))),
+            #[allow(unreachable_patterns)]
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Reading {:?} compressed input needs the {} feature",
+                    self,
+                    self.extension().unwrap_or_default()
+                ),
+            )),
+        }
+    }
+}
+
+/// The extension of `name` which gives its syntax, skipping any which
+/// gives its compression, so `ttl` for both `a.ttl` and `a.ttl.bz2`.
+pub fn syntax_extension(name: &str) -> Option<&str> {
+    let (stem, last) = split_extension(name)?;
+    if Compression::from_extension(last) == Compression::None {
+        Some(last)
+    } else {
+        split_extension(stem).map(|(_, extension)| extension)
+    }
+}
+
+// `name` split at its last dot, if it has one.
+fn split_extension(name: &str) -> Option<(&str, &str)> {
+    let mut parts = name.rsplitn(2, '.');
+    let extension = parts.next()?;
+    let stem = parts.next()?;
+    Some((stem, extension))
+}
+
+/// Open `path`, decompressing as it is read if need be.
+pub fn open(path: &Path) -> io::Result<Box<Read>> {
+    decompress(File::open(path)?)
+}
+
+/// A reader of the decompressed content of `reader`, recognising the
+/// compression by the first bytes.
+pub fn decompress<'a, R: Read + 'a>(mut reader: R) -> io::Result<Box<Read + 'a>> {
+    let mut magic = [0; MAGIC_LEN];
+    let mut len = 0;
+    while len < MAGIC_LEN {
+        match reader.read(&mut magic[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    let compression = Compression::from_magic(&magic[..len]);
+
+    // Put back what was read to look at.
+    let magic = Cursor::new(magic[..len].to_vec());
+    compression.decoder(magic.chain(reader))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn magic() {
+        assert_eq!(Compression::Gzip, Compression::from_magic(&[0x1f, 0x8b, 8]));
+        assert_eq!(Compression::Bzip2, Compression::from_magic(b"BZh91AY"));
+        assert_eq!(Compression::None, Compression::from_magic(b"<rdf:RDF"));
+        assert_eq!(Compression::None, Compression::from_magic(b""));
+    }
+
+    #[test]
+    fn extensions() {
+        assert_eq!(Some("nt"), syntax_extension("dump.nt.gz"));
+        assert_eq!(Some("ttl"), syntax_extension("a.ttl"));
+        assert_eq!(Some("owl"), syntax_extension("dir/go.owl.xz"));
+        assert_eq!(None, syntax_extension("README"));
+        assert_eq!(None, syntax_extension("gz"));
+        assert_eq!(None, syntax_extension("x.gz"));
+    }
+
+    #[test]
+    fn uncompressed() {
+        let content: &[u8] = b"<a> <b> <c> .\n";
+        let mut output = vec![];
+        decompress(content).unwrap().read_to_end(&mut output).unwrap();
+        assert_eq!(content, &output[..]);
+    }
+
+    // A file named as compressed which is not is read as it is.
+    #[test]
+    fn misnamed() {
+        let path =
+            std::env::temp_dir().join(format!("raptor-misnamed-{}.nt.gz", std::process::id()));
+        let content: &[u8] = b"<a> <b> <c> .\n";
+        std::fs::write(&path, content).unwrap();
+        let mut output = vec![];
+        let read = open(&path).and_then(|mut r| r.read_to_end(&mut output));
+        std::fs::remove_file(&path).unwrap();
+        read.unwrap();
+        assert_eq!(content, &output[..]);
+    }
+
+    #[cfg(feature = "gz")]
+    #[test]
+    fn gzip() {
+        use std::io::Write;
+
+        let content: &[u8] = b"<a> <b> <c> .\n";
+        let mut encoder =
+            ::flate2::write::GzEncoder::new(vec![], ::flate2::Compression::default());
+        encoder.write_all(content).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let mut output = vec![];
+        decompress(&compressed[..])
+            .unwrap()
+            .read_to_end(&mut output)
+            .unwrap();
+        assert_eq!(content, &output[..]);
+    }
+}
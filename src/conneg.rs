@@ -0,0 +1,256 @@
+//! HTTP content negotiation: `Accept` headers for fetching RDF, and
+//! choosing a serializer for a request's `Accept` header.
+use super::serializer::Serializer;
+use super::*;
+
+use std::ffi::CString;
+
+/// An `Accept` header for any of `syntaxes`, with libraptor's q-values
+/// for each, and anything else accepted at the lowest preference.
+///
+/// Syntaxes which this libraptor cannot parse are left out.
+pub fn accept_header(syntaxes: &[Syntax]) -> String {
+    let mut ranges: Vec<String> = vec![];
+    unsafe {
+        let world = raptor_new_world();
+        #[cfg(any(feature = "log", feature = "tracing"))]
+        logging::install(world);
+        for syntax in syntaxes {
+            let kind = CString::new(syntax.name()).unwrap();
+            let parser = raptor_new_parser(world, kind.as_ptr());
+            if parser.is_null() {
+                continue;
+            }
+            let header = raptor_parser_get_accept_header(parser);
+            let header = raptor_owned_string_to_rust_string(header as *mut u8);
+            raptor_free_parser(parser);
+
+            // Each parser adds its own catch all, which is added once
+            // at the end instead.
+            for range in header.split(',').map(|r| r.trim()) {
+                if !range.is_empty()
+                    && !range.starts_with("*/*")
+                    && !ranges.iter().any(|r| r == range)
+                {
+                    ranges.push(range.to_string());
+                }
+            }
+        }
+        raptor_free_world(world);
+    }
+    ranges.push("*/*;q=0.1".to_string());
+    ranges.join(", ")
+}
+
+/// The outcome of negotiation: the syntax to serialize to, and the
+/// Content-Type to send with it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Negotiated {
+    syntax: Syntax,
+    content_type: String,
+}
+
+impl Negotiated {
+    pub fn syntax(&self) -> Syntax {
+        self.syntax
+    }
+
+    pub fn content_type(&self) -> &str {
+        &self.content_type
+    }
+}
+
+/// Choose one of libraptor's serializers for an `Accept` header. An
+/// empty header accepts anything.
+pub fn choose_serializer(accept: &str) -> Option<Negotiated> {
+    choose(accept, &Serializer::descriptions())
+}
+
+/// Choose from `candidates` for an `Accept` header.
+///
+/// Each MIME type of each candidate is weighed by the q-value of the
+/// most specific range accepting it, and by the candidate's own
+/// quality; the best wins, the earlier on a tie. As libraptor lists a
+/// syntax's canonical MIME type first, that is the one chosen when
+/// only a wildcard matches.
+pub fn choose(accept: &str, candidates: &[SyntaxDescription]) -> Option<Negotiated> {
+    let ranges = parse_accept(accept);
+    let mut best: Option<(f32, Syntax, &str)> = None;
+
+    for candidate in candidates {
+        let syntax = match candidate.syntax() {
+            Some(syntax) => syntax,
+            None => continue,
+        };
+        for &(ref mime_type, quality) in candidate.mime_types() {
+            let score = accepted(&ranges, mime_type) * f32::from(quality);
+            if score > 0.0 && best.map_or(true, |(b, _, _)| score > b) {
+                best = Some((score, syntax, mime_type.as_str()));
+            }
+        }
+    }
+
+    best.map(|(_, syntax, content_type)| Negotiated {
+        syntax,
+        content_type: content_type.to_string(),
+    })
+}
+
+// A media range from an Accept header, such as `text/*;q=0.5`.
+#[derive(Debug)]
+struct MediaRange {
+    main: String,
+    sub: String,
+    q: f32,
+}
+
+impl MediaRange {
+    // How closely this range matches `mime_type`: 3 for exactly, 2 for
+    // `type/*`, 1 for `*/*`, or 0 for not at all.
+    fn specificity(&self, main: &str, sub: &str) -> u8 {
+        if self.main == "*" {
+            1
+        } else if !self.main.eq_ignore_ascii_case(main) {
+            0
+        } else if self.sub == "*" {
+            2
+        } else if self.sub.eq_ignore_ascii_case(sub) {
+            3
+        } else {
+            0
+        }
+    }
+}
+
+fn parse_accept(accept: &str) -> Vec<MediaRange> {
+    if accept.trim().is_empty() {
+        return vec![MediaRange {
+            main: "*".to_string(),
+            sub: "*".to_string(),
+            q: 1.0,
+        }];
+    }
+
+    accept
+        .split(',')
+        .filter_map(|range| {
+            let mut parameters = range.split(';').map(|p| p.trim());
+            let mut mime_type = parameters.next()?.splitn(2, '/');
+            let main = mime_type.next()?.trim();
+            let sub = mime_type.next()?.trim();
+            if main.is_empty() || sub.is_empty() {
+                return None;
+            }
+
+            // An unreadable q-value is taken as the default.
+            let q = parameters
+                .filter_map(|p| {
+                    let mut pair = p.splitn(2, '=');
+                    match (pair.next(), pair.next()) {
+                        (Some(name), Some(value)) if name.trim().eq_ignore_ascii_case("q") => {
+                            value.trim().parse::<f32>().ok()
+                        }
+                        _ => None,
+                    }
+                })
+                .next()
+                .unwrap_or(1.0);
+
+            Some(MediaRange {
+                main: main.to_string(),
+                sub: sub.to_string(),
+                q: q.max(0.0).min(1.0),
+            })
+        })
+        .collect()
+}
+
+// The q-value of the most specific range accepting `mime_type`.
+fn accepted(ranges: &[MediaRange], mime_type: &str) -> f32 {
+    let mut parts = mime_type.splitn(2, '/');
+    let main = parts.next().unwrap_or("");
+    let sub = parts.next().unwrap_or("");
+
+    ranges
+        .iter()
+        .map(|range| (range.specificity(main, sub), range.q))
+        .filter(|&(specificity, _)| specificity > 0)
+        .max_by_key(|&(specificity, _)| specificity)
+        .map_or(0.0, |(_, q)| q)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn description(name: &str, mime_types: &[(&str, u8)]) -> SyntaxDescription {
+        SyntaxDescription {
+            names: vec![name.to_string()],
+            label: name.to_string(),
+            mime_types: mime_types
+                .iter()
+                .map(|&(m, q)| (m.to_string(), q))
+                .collect(),
+            uris: vec![],
+        }
+    }
+
+    fn candidates() -> Vec<SyntaxDescription> {
+        vec![
+            description("ntriples", &[("application/n-triples", 10), ("text/plain", 1)]),
+            description(
+                "turtle",
+                &[("text/turtle", 10), ("application/x-turtle", 10)],
+            ),
+            description("rdfxml", &[("application/rdf+xml", 10)]),
+        ]
+    }
+
+    fn chosen(accept: &str) -> Option<(Syntax, String)> {
+        choose(accept, &candidates()).map(|n| (n.syntax(), n.content_type().to_string()))
+    }
+
+    #[test]
+    fn exact() {
+        assert_eq!(
+            Some((Syntax::RdfXml, "application/rdf+xml".to_string())),
+            chosen("application/rdf+xml")
+        );
+        assert_eq!(
+            Some((Syntax::Turtle, "application/x-turtle".to_string())),
+            chosen("application/x-turtle")
+        );
+    }
+
+    #[test]
+    fn q_values() {
+        assert_eq!(
+            Some(Syntax::Turtle),
+            chosen("application/rdf+xml;q=0.5, text/turtle").map(|c| c.0)
+        );
+        assert_eq!(
+            Some(Syntax::RdfXml),
+            chosen("text/*;q=0, application/rdf+xml;q=0.1").map(|c| c.0)
+        );
+    }
+
+    #[test]
+    fn wildcards() {
+        assert_eq!(Some(Syntax::NTriples), chosen("*/*").map(|c| c.0));
+        assert_eq!(Some(Syntax::NTriples), chosen("").map(|c| c.0));
+        assert_eq!(
+            Some((Syntax::Turtle, "text/turtle".to_string())),
+            chosen("text/*")
+        );
+        assert_eq!(None, chosen("image/png"));
+    }
+
+    #[test]
+    fn header() {
+        let header = accept_header(&[Syntax::Turtle, Syntax::RdfXml]);
+        assert!(header.contains("text/turtle"));
+        assert!(header.contains("application/rdf+xml"));
+        assert!(header.ends_with("*/*;q=0.1"));
+        assert_eq!(1, header.matches("*/*").count());
+    }
+}
@@ -0,0 +1,608 @@
+use super::*;
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::hash::Hasher;
+use std::iter::FromIterator;
+
+/// An in-memory set of statements.
+///
+/// Statements are kept ordered and without duplicates, so that two
+/// graphs containing the same statements compare equal regardless of
+/// the order in which they were parsed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Graph {
+    statements: BTreeSet<Statement>,
+}
+
+impl Graph {
+    pub fn new() -> Graph {
+        Graph::default()
+    }
+
+    pub fn insert(&mut self, statement: Statement) -> bool {
+        self.statements.insert(statement)
+    }
+
+    pub fn remove(&mut self, statement: &Statement) -> bool {
+        self.statements.remove(statement)
+    }
+
+    pub fn contains(&self, statement: &Statement) -> bool {
+        self.statements.contains(statement)
+    }
+
+    pub fn len(&self) -> usize {
+        self.statements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Statement> {
+        self.statements.iter()
+    }
+
+    /// Objects of all statements with this subject and predicate.
+    pub fn objects<'a, 'g: 'a>(
+        &'g self,
+        subject: &'a Term,
+        predicate: &'a Term,
+    ) -> impl Iterator<Item = &'g Term> + 'a {
+        self.statements
+            .iter()
+            .filter(move |s| &s.subject == subject && &s.predicate == predicate)
+            .map(|s| &s.object)
+    }
+
+    /// Subjects of all statements with this predicate and object.
+    pub fn subjects<'a, 'g: 'a>(
+        &'g self,
+        predicate: &'a Term,
+        object: &'a Term,
+    ) -> impl Iterator<Item = &'g Term> + 'a {
+        self.statements
+            .iter()
+            .filter(move |s| &s.predicate == predicate && &s.object == object)
+            .map(|s| &s.subject)
+    }
+
+    /// Are these two graphs the same, up to a renaming of blank nodes?
+    ///
+    /// Blank node labels are local to a document, so two parses of the
+    /// same data (or a parse and its expected N-Triples) will rarely
+    /// use the same labels. Statements without blank nodes must match
+    /// exactly; the remainder are matched by searching for a bijection
+    /// between the blank nodes of each graph.
+    pub fn is_isomorphic(&self, other: &Graph) -> bool {
+        if self.len() != other.len() {
+            return false;
+        }
+
+        let (ground_a, blank_a) = partition(self);
+        let (ground_b, blank_b) = partition(other);
+
+        if ground_a != ground_b || blank_a.len() != blank_b.len() {
+            return false;
+        }
+
+        let nodes_a = blank_nodes(&blank_a);
+        let nodes_b = blank_nodes(&blank_b);
+        if nodes_a.len() != nodes_b.len() {
+            return false;
+        }
+
+        let (colors_a, colors_b) = refine_colors(&blank_a, &nodes_a, &blank_b, &nodes_b);
+
+        let mut sorted_a: Vec<u64> = colors_a.values().cloned().collect();
+        let mut sorted_b: Vec<u64> = colors_b.values().cloned().collect();
+        sorted_a.sort();
+        sorted_b.sort();
+        if sorted_a != sorted_b {
+            return false;
+        }
+
+        let mut candidates: HashMap<u64, Vec<&str>> = HashMap::new();
+        for node in &nodes_b {
+            candidates
+                .entry(colors_b[node])
+                .or_insert_with(Vec::new)
+                .push(*node);
+        }
+
+        // Search the most constrained nodes first.
+        let mut order: Vec<&str> = nodes_a.clone();
+        order.sort_by_key(|node| candidates[&colors_a[node]].len());
+
+        let target: BTreeSet<Statement> = blank_b.into_iter().cloned().collect();
+        let mut search = Search {
+            statements: &blank_a,
+            target: &target,
+            colors: &colors_a,
+            candidates: &candidates,
+            mapping: HashMap::new(),
+            used: HashSet::new(),
+        };
+        search.run(&order)
+    }
+
+    /// The statements which must be removed from this graph, and added
+    /// to it, to give `other`.
+    ///
+    /// Statements without blank nodes are compared directly. Those with
+    /// blank nodes are grouped into the connected structures they form,
+    /// such as an OWL restriction, and a structure is unchanged if
+    /// `other` has one isomorphic to it. A structure which has changed
+    /// at all is removed and added in full, as there is no telling
+    /// which of its blank nodes correspond.
+    pub fn diff(&self, other: &Graph) -> Diff {
+        let (ground_a, blank_a) = partition(self);
+        let (ground_b, blank_b) = partition(other);
+
+        let mut removed: Graph = ground_a
+            .difference(&ground_b)
+            .map(|s| (*s).clone())
+            .collect();
+        let mut added: Graph = ground_b
+            .difference(&ground_a)
+            .map(|s| (*s).clone())
+            .collect();
+
+        let mut unmatched: HashMap<Vec<u64>, Vec<Graph>> = HashMap::new();
+        for component in components(&blank_b) {
+            unmatched
+                .entry(shape(&component))
+                .or_insert_with(Vec::new)
+                .push(component);
+        }
+
+        for component in components(&blank_a) {
+            let matched = unmatched
+                .get_mut(&shape(&component))
+                .and_then(|candidates| {
+                    candidates
+                        .iter()
+                        .position(|c| c.is_isomorphic(&component))
+                        .map(|i| candidates.swap_remove(i))
+                });
+            if matched.is_none() {
+                removed.extend(component);
+            }
+        }
+
+        for component in unmatched.into_iter().flat_map(|(_, c)| c) {
+            added.extend(component);
+        }
+
+        Diff { removed, added }
+    }
+}
+
+/// The difference between two graphs, from `Graph::diff`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Diff {
+    removed: Graph,
+    added: Graph,
+}
+
+impl Diff {
+    pub fn removed(&self) -> &Graph {
+        &self.removed
+    }
+
+    pub fn added(&self) -> &Graph {
+        &self.added
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty()
+    }
+}
+
+impl FromIterator<Statement> for Graph {
+    fn from_iter<I: IntoIterator<Item = Statement>>(iter: I) -> Graph {
+        Graph {
+            statements: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<Statement> for Graph {
+    fn extend<I: IntoIterator<Item = Statement>>(&mut self, iter: I) {
+        self.statements.extend(iter)
+    }
+}
+
+impl IntoIterator for Graph {
+    type Item = Statement;
+    type IntoIter = ::std::collections::btree_set::IntoIter<Statement>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.statements.into_iter()
+    }
+}
+
+fn blank_label(term: &Term) -> Option<&str> {
+    match term {
+        Term::Blank(label) => Some(label.as_str()),
+        _ => None,
+    }
+}
+
+fn statement_terms(statement: &Statement) -> Vec<&Term> {
+    let mut terms = vec![&statement.subject, &statement.predicate, &statement.object];
+    if let Some(ref graph) = statement.graph {
+        terms.push(graph);
+    }
+    terms
+}
+
+fn has_blank(statement: &Statement) -> bool {
+    statement_terms(statement)
+        .iter()
+        .any(|t| blank_label(t).is_some())
+}
+
+fn partition(graph: &Graph) -> (BTreeSet<&Statement>, Vec<&Statement>) {
+    let mut ground = BTreeSet::new();
+    let mut blank = Vec::new();
+    for statement in graph.iter() {
+        if has_blank(statement) {
+            blank.push(statement);
+        } else {
+            ground.insert(statement);
+        }
+    }
+    (ground, blank)
+}
+
+fn blank_nodes<'a>(statements: &[&'a Statement]) -> Vec<&'a str> {
+    let mut seen = BTreeSet::new();
+    for &statement in statements {
+        for term in statement_terms(statement) {
+            if let Some(label) = blank_label(term) {
+                seen.insert(label);
+            }
+        }
+    }
+    seen.into_iter().collect()
+}
+
+fn hash_of<T: Hash>(value: T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn term_color(term: &Term, colors: &HashMap<&str, u64>) -> u64 {
+    match blank_label(term) {
+        Some(label) => hash_of(("blank", colors[label])),
+        None => hash_of(term),
+    }
+}
+
+// A single round of colour refinement: each blank node is recoloured
+// by its old colour plus the statements it appears in, with every
+// blank node in those statements replaced by its colour.
+fn refine<'a>(
+    statements: &[&'a Statement],
+    colors: &HashMap<&'a str, u64>,
+) -> HashMap<&'a str, u64> {
+    let mut signatures: HashMap<&str, Vec<u64>> = HashMap::new();
+    for &statement in statements {
+        let terms = statement_terms(statement);
+        let term_colors: Vec<u64> = terms.iter().map(|t| term_color(t, colors)).collect();
+        for (position, term) in terms.iter().enumerate() {
+            if let Some(label) = blank_label(term) {
+                signatures
+                    .entry(label)
+                    .or_insert_with(Vec::new)
+                    .push(hash_of((position, &term_colors)));
+            }
+        }
+    }
+
+    colors
+        .iter()
+        .map(|(label, color)| {
+            let mut signature = signatures.remove(label).unwrap_or_default();
+            signature.sort();
+            (*label, hash_of((color, signature)))
+        })
+        .collect()
+}
+
+fn distinct(colors: &HashMap<&str, u64>) -> usize {
+    colors.values().collect::<HashSet<_>>().len()
+}
+
+fn refine_colors<'a, 'b>(
+    statements_a: &[&'a Statement],
+    nodes_a: &[&'a str],
+    statements_b: &[&'b Statement],
+    nodes_b: &[&'b str],
+) -> (HashMap<&'a str, u64>, HashMap<&'b str, u64>) {
+    let mut colors_a: HashMap<&str, u64> = nodes_a.iter().map(|n| (*n, 0)).collect();
+    let mut colors_b: HashMap<&str, u64> = nodes_b.iter().map(|n| (*n, 0)).collect();
+
+    // Both graphs must be refined the same number of times for their
+    // colours to be comparable.
+    loop {
+        let next_a = refine(statements_a, &colors_a);
+        let next_b = refine(statements_b, &colors_b);
+        let stable =
+            distinct(&next_a) == distinct(&colors_a) && distinct(&next_b) == distinct(&colors_b);
+        colors_a = next_a;
+        colors_b = next_b;
+        if stable {
+            return (colors_a, colors_b);
+        }
+    }
+}
+
+// Group statements into sets connected by shared blank nodes.
+fn components(statements: &[&Statement]) -> Vec<Graph> {
+    let mut parents: Vec<usize> = vec![];
+    let mut ids: HashMap<&str, usize> = HashMap::new();
+
+    fn root(parents: &mut Vec<usize>, mut id: usize) -> usize {
+        while parents[id] != id {
+            parents[id] = parents[parents[id]];
+            id = parents[id];
+        }
+        id
+    }
+
+    for &statement in statements {
+        let mut first = None;
+        for label in statement_terms(statement).into_iter().filter_map(blank_label) {
+            let id = *ids.entry(label).or_insert_with(|| {
+                parents.push(parents.len());
+                parents.len() - 1
+            });
+            match first {
+                None => first = Some(id),
+                Some(first) => {
+                    let a = root(&mut parents, first);
+                    let b = root(&mut parents, id);
+                    parents[b] = a;
+                }
+            }
+        }
+    }
+
+    let mut components: HashMap<usize, Graph> = HashMap::new();
+    for &statement in statements {
+        let label = statement_terms(statement)
+            .into_iter()
+            .filter_map(blank_label)
+            .next()
+            .unwrap();
+        let id = root(&mut parents, ids[label]);
+        components
+            .entry(id)
+            .or_insert_with(Graph::new)
+            .insert(statement.clone());
+    }
+    components.into_iter().map(|(_, c)| c).collect()
+}
+
+// A summary of a graph which does not depend on its blank node labels,
+// so that only graphs with the same shape need be tested for
+// isomorphism.
+fn shape(graph: &Graph) -> Vec<u64> {
+    let mut shape: Vec<u64> = graph
+        .iter()
+        .map(|statement| {
+            let terms: Vec<u64> = statement_terms(statement)
+                .iter()
+                .map(|t| match blank_label(t) {
+                    Some(_) => 0,
+                    None => hash_of(t),
+                })
+                .collect();
+            hash_of(terms)
+        })
+        .collect();
+    shape.sort();
+    shape
+}
+
+fn relabel(term: &Term, mapping: &HashMap<&str, &str>) -> Term {
+    match blank_label(term) {
+        Some(label) => Term::Blank(mapping[label].to_string()),
+        None => term.clone(),
+    }
+}
+
+struct Search<'a, 'b: 'a> {
+    statements: &'a [&'a Statement],
+    target: &'a BTreeSet<Statement>,
+    colors: &'a HashMap<&'a str, u64>,
+    candidates: &'a HashMap<u64, Vec<&'b str>>,
+    mapping: HashMap<&'a str, &'b str>,
+    used: HashSet<&'b str>,
+}
+
+impl<'a, 'b: 'a> Search<'a, 'b> {
+    fn run(&mut self, order: &[&'a str]) -> bool {
+        let (node, rest) = match order.split_first() {
+            Some(split) => split,
+            None => return true,
+        };
+
+        let candidates = self.candidates;
+        for candidate in &candidates[&self.colors[node]] {
+            if self.used.contains(candidate) {
+                continue;
+            }
+            self.mapping.insert(*node, *candidate);
+            self.used.insert(*candidate);
+
+            if self.consistent(node) && self.run(rest) {
+                return true;
+            }
+
+            self.mapping.remove(node);
+            self.used.remove(candidate);
+        }
+        false
+    }
+
+    // Check every statement mentioning `node` whose blank nodes have
+    // all been mapped.
+    fn consistent(&self, node: &str) -> bool {
+        let mapping = &self.mapping;
+        self.statements
+            .iter()
+            .filter(|statement| {
+                let labels: Vec<&str> = statement_terms(statement)
+                    .into_iter()
+                    .filter_map(blank_label)
+                    .collect();
+                labels.contains(&node) && labels.iter().all(|l| mapping.contains_key(l))
+            })
+            .all(|statement| {
+                let mapped = Statement {
+                    subject: relabel(&statement.subject, mapping),
+                    predicate: relabel(&statement.predicate, mapping),
+                    object: relabel(&statement.object, mapping),
+                    graph: statement.graph.as_ref().map(|g| relabel(g, mapping)),
+                };
+                self.target.contains(&mapped)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn iri(s: &str) -> Term {
+        Term::URI(IRI::new(s))
+    }
+
+    fn blank(s: &str) -> Term {
+        Term::Blank(s.to_string())
+    }
+
+    fn triple(s: Term, p: Term, o: Term) -> Statement {
+        Statement::new(s, p, o, None)
+    }
+
+    #[test]
+    fn ground_graphs() {
+        let a: Graph = vec![triple(iri("a"), iri("p"), iri("b"))].into_iter().collect();
+        let b: Graph = vec![triple(iri("a"), iri("p"), iri("b"))].into_iter().collect();
+        let c: Graph = vec![triple(iri("a"), iri("p"), iri("c"))].into_iter().collect();
+
+        assert!(a.is_isomorphic(&b));
+        assert!(!a.is_isomorphic(&c));
+    }
+
+    #[test]
+    fn renamed_blank_nodes() {
+        let a: Graph = vec![
+            triple(blank("x"), iri("p"), blank("y")),
+            triple(blank("y"), iri("q"), iri("o")),
+        ]
+        .into_iter()
+        .collect();
+        let b: Graph = vec![
+            triple(blank("genid2"), iri("p"), blank("genid1")),
+            triple(blank("genid1"), iri("q"), iri("o")),
+        ]
+        .into_iter()
+        .collect();
+
+        assert!(a.is_isomorphic(&b));
+    }
+
+    #[test]
+    fn blank_structure_differs() {
+        let a: Graph = vec![
+            triple(blank("x"), iri("p"), blank("y")),
+            triple(blank("y"), iri("p"), blank("x")),
+        ]
+        .into_iter()
+        .collect();
+        let b: Graph = vec![
+            triple(blank("x"), iri("p"), blank("y")),
+            triple(blank("y"), iri("p"), blank("z")),
+        ]
+        .into_iter()
+        .collect();
+
+        assert!(!a.is_isomorphic(&b));
+    }
+
+    #[test]
+    fn diff() {
+        let a: Graph = vec![
+            triple(iri("a"), iri("p"), iri("b")),
+            triple(iri("a"), iri("p"), iri("c")),
+            triple(iri("a"), iri("q"), blank("x")),
+            triple(blank("x"), iri("r"), iri("d")),
+            triple(iri("a"), iri("q"), blank("y")),
+            triple(blank("y"), iri("r"), iri("e")),
+        ]
+        .into_iter()
+        .collect();
+        let b: Graph = vec![
+            triple(iri("a"), iri("p"), iri("b")),
+            triple(iri("a"), iri("p"), iri("f")),
+            triple(iri("a"), iri("q"), blank("genid1")),
+            triple(blank("genid1"), iri("r"), iri("d")),
+            triple(iri("a"), iri("q"), blank("genid2")),
+            triple(blank("genid2"), iri("r"), iri("g")),
+        ]
+        .into_iter()
+        .collect();
+
+        let diff = a.diff(&b);
+        let removed: Graph = vec![
+            triple(iri("a"), iri("p"), iri("c")),
+            triple(iri("a"), iri("q"), blank("y")),
+            triple(blank("y"), iri("r"), iri("e")),
+        ]
+        .into_iter()
+        .collect();
+        let added: Graph = vec![
+            triple(iri("a"), iri("p"), iri("f")),
+            triple(iri("a"), iri("q"), blank("genid2")),
+            triple(blank("genid2"), iri("r"), iri("g")),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(&removed, diff.removed());
+        assert_eq!(&added, diff.added());
+
+        assert!(a.diff(&a.clone()).is_empty());
+    }
+
+    #[test]
+    fn symmetric_blank_nodes() {
+        // Colour refinement alone cannot tell these nodes apart, so
+        // this exercises the backtracking search.
+        let a: Graph = vec![
+            triple(blank("a"), iri("p"), blank("b")),
+            triple(blank("b"), iri("p"), blank("c")),
+            triple(blank("c"), iri("p"), blank("a")),
+        ]
+        .into_iter()
+        .collect();
+        let b: Graph = vec![
+            triple(blank("z"), iri("p"), blank("x")),
+            triple(blank("x"), iri("p"), blank("y")),
+            triple(blank("y"), iri("p"), blank("z")),
+        ]
+        .into_iter()
+        .collect();
+
+        assert!(a.is_isomorphic(&b));
+    }
+}
@@ -0,0 +1,249 @@
+//! Matching statements against triple patterns while they stream past,
+//! so that large files can be searched without building a graph.
+use super::namespaces::Namespaces;
+use super::push::ParserHandler;
+use super::*;
+
+use regex::Regex;
+
+/// A pattern for one position of a statement.
+#[derive(Clone, Debug)]
+pub enum TermPattern {
+    /// Matches any term; written `?`, `*` or as an empty string.
+    Any,
+    /// Matches exactly this IRI; written `<http://...>`, or bare if it
+    /// contains `://`.
+    IRI(IRI),
+    /// A prefixed name such as `dc:title`, expanded with the prefixes
+    /// known when the statement is matched.
+    Curie(String, String),
+    /// Matches literals whose value matches a regex; written `/.../`.
+    Regex(Regex),
+    /// Matches literals with a language tag; written `@en`. As with
+    /// SPARQL's `langMatches`, `@en` also matches `en-GB`.
+    Lang(String),
+}
+
+impl TermPattern {
+    pub fn parse(pattern: &str) -> Result<TermPattern, String> {
+        if pattern.is_empty() || pattern == "?" || pattern == "*" {
+            return Ok(TermPattern::Any);
+        }
+
+        if pattern.len() >= 2 && pattern.starts_with('<') && pattern.ends_with('>') {
+            return Ok(TermPattern::IRI(IRI::new(&pattern[1..pattern.len() - 1])));
+        }
+
+        if pattern.len() >= 2 && pattern.starts_with('/') && pattern.ends_with('/') {
+            return Regex::new(&pattern[1..pattern.len() - 1])
+                .map(TermPattern::Regex)
+                .map_err(|e| e.to_string());
+        }
+
+        if pattern.starts_with('@') && pattern.len() > 1 {
+            return Ok(TermPattern::Lang(pattern[1..].to_lowercase()));
+        }
+
+        if pattern.contains("://") {
+            return Ok(TermPattern::IRI(IRI::new(pattern)));
+        }
+
+        match pattern.find(':') {
+            Some(i) => Ok(TermPattern::Curie(
+                pattern[..i].to_string(),
+                pattern[i + 1..].to_string(),
+            )),
+            None => Err(format!("Cannot understand pattern: {}", pattern)),
+        }
+    }
+
+    /// Does `term` match, given the prefixes known so far? The default
+    /// namespace has the empty prefix.
+    pub fn matches(&self, term: &Term, prefixes: &Namespaces) -> bool {
+        match (self, term) {
+            (&TermPattern::Any, _) => true,
+            (&TermPattern::IRI(ref pattern), &Term::URI(ref iri)) => pattern == iri,
+            (&TermPattern::Curie(ref prefix, ref local), &Term::URI(ref iri)) => {
+                match prefixes.get(prefix) {
+                    Some(namespace) => {
+                        iri.as_str().starts_with(namespace.as_str())
+                            && &iri.as_str()[namespace.as_str().len()..] == local
+                    }
+                    None => false,
+                }
+            }
+            (&TermPattern::Regex(ref regex), &Term::Literal(ref literal)) => {
+                regex.is_match(literal.value())
+            }
+            (&TermPattern::Lang(ref pattern), &Term::Literal(ref literal)) => {
+                match literal.lang() {
+                    Some(lang) => {
+                        let lang = lang.to_lowercase();
+                        lang == *pattern || lang.starts_with(&format!("{}-", pattern))
+                    }
+                    None => false,
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A pattern for a whole statement, along with the prefixes used to
+/// expand its CURIEs.
+#[derive(Clone, Debug)]
+pub struct TriplePattern {
+    subject: TermPattern,
+    predicate: TermPattern,
+    object: TermPattern,
+    prefixes: Namespaces,
+}
+
+impl TriplePattern {
+    pub fn new(subject: TermPattern, predicate: TermPattern, object: TermPattern) -> TriplePattern {
+        TriplePattern {
+            subject,
+            predicate,
+            object,
+            prefixes: Namespaces::new(),
+        }
+    }
+
+    /// Add a prefix for expanding CURIEs. Prefixes declared by the
+    /// document being searched replace these.
+    pub fn prefix(mut self, prefix: &str, iri: IRI) -> TriplePattern {
+        self.prefixes.insert(prefix, iri);
+        self
+    }
+
+    pub fn declare(&mut self, namespace: &Namespace) {
+        self.prefixes.declare(namespace);
+    }
+
+    pub fn matches(&self, statement: &Statement) -> bool {
+        self.subject.matches(statement.subject(), &self.prefixes)
+            && self.predicate.matches(statement.predicate(), &self.prefixes)
+            && self.object.matches(statement.object(), &self.prefixes)
+    }
+}
+
+/// Passes on only those statements matching a `TriplePattern`, learning
+/// prefixes from the document as it goes.
+#[derive(Debug)]
+pub struct Grep<H> {
+    pattern: TriplePattern,
+    handler: H,
+}
+
+impl<H> Grep<H> {
+    pub fn new(pattern: TriplePattern, handler: H) -> Grep<H> {
+        Grep { pattern, handler }
+    }
+
+    pub fn into_inner(self) -> H {
+        self.handler
+    }
+}
+
+impl<H: ParserHandler> ParserHandler for Grep<H> {
+    fn handle_statement(&mut self, statement: Statement) -> Result<(), String> {
+        if self.pattern.matches(&statement) {
+            self.handler.handle_statement(statement)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn handle_error(&mut self, error: LogMessage) -> Result<(), String> {
+        self.handler.handle_error(error)
+    }
+
+    fn handle_namespace(&mut self, namespace: Namespace) -> Result<(), String> {
+        self.pattern.declare(&namespace);
+        self.handler.handle_namespace(namespace)
+    }
+
+    fn handle_located_statement(
+        &mut self,
+        statement: Statement,
+        locator: Locator,
+    ) -> Result<(), String> {
+        if self.pattern.matches(&statement) {
+            self.handler.handle_located_statement(statement, locator)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use push::MemoryParserHandler;
+    use push::Parser;
+
+    fn grep(pattern: TriplePattern) -> usize {
+        let about = include_str!("./test-files/about_two.rdf");
+        let mut m = MemoryParserHandler::new();
+        {
+            let h = Grep::new(pattern, &mut m);
+            let mut p = Parser::new("rdfxml", "http://www.example.com", &h);
+            p.parse_chunk(about);
+            p.parse_complete();
+        }
+        m.0.len()
+    }
+
+    fn pattern(s: &str, p: &str, o: &str) -> TriplePattern {
+        TriplePattern::new(
+            TermPattern::parse(s).unwrap(),
+            TermPattern::parse(p).unwrap(),
+            TermPattern::parse(o).unwrap(),
+        )
+    }
+
+    #[test]
+    fn parse_patterns() {
+        match TermPattern::parse("<http://www.example.com/>").unwrap() {
+            TermPattern::IRI(iri) => assert_eq!("http://www.example.com/", iri.as_str()),
+            p => panic!("{:?}", p),
+        }
+        match TermPattern::parse("dc:title").unwrap() {
+            TermPattern::Curie(prefix, local) => assert_eq!(("dc", "title"), (&*prefix, &*local)),
+            p => panic!("{:?}", p),
+        }
+        assert!(TermPattern::parse("?").is_ok());
+        assert!(TermPattern::parse("/[/").is_err());
+        assert!(TermPattern::parse("title").is_err());
+    }
+
+    #[test]
+    fn lang() {
+        let prefixes = Namespaces::new();
+        let en_gb = Term::Literal(Literal::new("colour", None, Some("en-GB")));
+        assert!(TermPattern::parse("@en").unwrap().matches(&en_gb, &prefixes));
+        assert!(TermPattern::parse("@EN-gb").unwrap().matches(&en_gb, &prefixes));
+        assert!(!TermPattern::parse("@eng").unwrap().matches(&en_gb, &prefixes));
+    }
+
+    #[test]
+    fn grep_parse() {
+        assert_eq!(2, grep(pattern("?", "?", "?")));
+        assert_eq!(2, grep(pattern("?", "dc:title", "?")));
+        assert_eq!(
+            2,
+            grep(pattern("?", "<http://purl.org/dc/elements/1.1/title>", "?"))
+        );
+        assert_eq!(1, grep(pattern("?", "?", "/Resource/")));
+        assert_eq!(1, grep(pattern("<http://www.example.com/>", "?", "/^Example$/")));
+        assert_eq!(0, grep(pattern("?", "?", "/^Resource/")));
+        assert_eq!(0, grep(pattern("?", "ex:title", "?")));
+        assert_eq!(
+            2,
+            grep(
+                pattern("?", "ex:title", "?")
+                    .prefix("ex", IRI::new("http://purl.org/dc/elements/1.1/"))
+            )
+        );
+    }
+}
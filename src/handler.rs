@@ -0,0 +1,463 @@
+//! Adapters for building `ParserHandler`s out of closures and other
+//! handlers, without having to declare a new type each time.
+use super::push::ParserHandler;
+use super::*;
+
+/// A `ParserHandler` made from closures.
+///
+/// Events without a closure are ignored. A closure returning an error
+/// stops the parse.
+///
+/// ```ignore
+/// let mut count = 0;
+/// let h = FnHandler::new().on_statement(|_| {
+///     count += 1;
+///     Ok(())
+/// });
+/// ```
+#[derive(Default)]
+pub struct FnHandler<'a> {
+    statement: Option<Box<FnMut(Statement) -> Result<(), String> + 'a>>,
+    error: Option<Box<FnMut(LogMessage) -> Result<(), String> + 'a>>,
+    namespace: Option<Box<FnMut(Namespace) -> Result<(), String> + 'a>>,
+    located: Option<Box<FnMut(Statement, Locator) -> Result<(), String> + 'a>>,
+}
+
+impl<'a> FnHandler<'a> {
+    pub fn new() -> FnHandler<'a> {
+        FnHandler::default()
+    }
+
+    pub fn on_statement<F>(mut self, f: F) -> FnHandler<'a>
+    where
+        F: FnMut(Statement) -> Result<(), String> + 'a,
+    {
+        self.statement = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_error<F>(mut self, f: F) -> FnHandler<'a>
+    where
+        F: FnMut(LogMessage) -> Result<(), String> + 'a,
+    {
+        self.error = Some(Box::new(f));
+        self
+    }
+
+    pub fn on_namespace<F>(mut self, f: F) -> FnHandler<'a>
+    where
+        F: FnMut(Namespace) -> Result<(), String> + 'a,
+    {
+        self.namespace = Some(Box::new(f));
+        self
+    }
+
+    /// Receive statements with their locators, from a parser capturing
+    /// them. Otherwise statements go to `on_statement`.
+    pub fn on_located_statement<F>(mut self, f: F) -> FnHandler<'a>
+    where
+        F: FnMut(Statement, Locator) -> Result<(), String> + 'a,
+    {
+        self.located = Some(Box::new(f));
+        self
+    }
+}
+
+/// An `on_error` closure, as the command line tools use, which prints
+/// each message to stderr, after `file` if there is one, and counts
+/// those at `Error` or worse in `errors`.
+pub fn report_errors<'a>(
+    file: Option<&'a str>,
+    errors: &'a mut usize,
+) -> impl FnMut(LogMessage) -> Result<(), String> + 'a {
+    move |message| {
+        if message.level() >= LogLevel::Error {
+            *errors += 1;
+        }
+        match file {
+            Some(file) => eprintln!("{}: {}", file, message),
+            None => eprintln!("{}", message),
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Debug for FnHandler<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FnHandler")
+            .field("statement", &self.statement.is_some())
+            .field("error", &self.error.is_some())
+            .field("namespace", &self.namespace.is_some())
+            .field("located", &self.located.is_some())
+            .finish()
+    }
+}
+
+impl<'a> ParserHandler for FnHandler<'a> {
+    fn handle_statement(&mut self, statement: Statement) -> Result<(), String> {
+        match self.statement {
+            Some(ref mut f) => f(statement),
+            None => Ok(()),
+        }
+    }
+
+    fn handle_error(&mut self, error: LogMessage) -> Result<(), String> {
+        match self.error {
+            Some(ref mut f) => f(error),
+            None => Ok(()),
+        }
+    }
+
+    fn handle_namespace(&mut self, namespace: Namespace) -> Result<(), String> {
+        match self.namespace {
+            Some(ref mut f) => f(namespace),
+            None => Ok(()),
+        }
+    }
+
+    fn handle_located_statement(
+        &mut self,
+        statement: Statement,
+        locator: Locator,
+    ) -> Result<(), String> {
+        match self.located {
+            Some(ref mut f) => f(statement, locator),
+            None => self.handle_statement(statement),
+        }
+    }
+}
+
+/// Sends every event to two handlers.
+#[derive(Debug)]
+pub struct Tee<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> Tee<A, B> {
+    pub fn into_inner(self) -> (A, B) {
+        (self.first, self.second)
+    }
+}
+
+impl<A: ParserHandler, B: ParserHandler> ParserHandler for Tee<A, B> {
+    fn handle_statement(&mut self, statement: Statement) -> Result<(), String> {
+        let first = self.first.handle_statement(statement.clone());
+        let second = self.second.handle_statement(statement);
+        first.and(second)
+    }
+
+    fn handle_error(&mut self, error: LogMessage) -> Result<(), String> {
+        let first = self.first.handle_error(error.clone());
+        let second = self.second.handle_error(error);
+        first.and(second)
+    }
+
+    fn handle_namespace(&mut self, namespace: Namespace) -> Result<(), String> {
+        let first = self.first.handle_namespace(namespace.clone());
+        let second = self.second.handle_namespace(namespace);
+        first.and(second)
+    }
+
+    fn handle_located_statement(
+        &mut self,
+        statement: Statement,
+        locator: Locator,
+    ) -> Result<(), String> {
+        let first = self
+            .first
+            .handle_located_statement(statement.clone(), locator.clone());
+        let second = self.second.handle_located_statement(statement, locator);
+        first.and(second)
+    }
+}
+
+/// Passes on only those statements matching a predicate.
+pub struct Filter<H, P> {
+    handler: H,
+    predicate: P,
+}
+
+impl<H, P> Filter<H, P> {
+    pub fn into_inner(self) -> H {
+        self.handler
+    }
+}
+
+impl<H: Debug, P> Debug for Filter<H, P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Filter")
+            .field("handler", &self.handler)
+            .finish()
+    }
+}
+
+impl<H, P> ParserHandler for Filter<H, P>
+where
+    H: ParserHandler,
+    P: FnMut(&Statement) -> bool,
+{
+    fn handle_statement(&mut self, statement: Statement) -> Result<(), String> {
+        if (self.predicate)(&statement) {
+            self.handler.handle_statement(statement)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn handle_error(&mut self, error: LogMessage) -> Result<(), String> {
+        self.handler.handle_error(error)
+    }
+
+    fn handle_namespace(&mut self, namespace: Namespace) -> Result<(), String> {
+        self.handler.handle_namespace(namespace)
+    }
+
+    fn handle_located_statement(
+        &mut self,
+        statement: Statement,
+        locator: Locator,
+    ) -> Result<(), String> {
+        if (self.predicate)(&statement) {
+            self.handler.handle_located_statement(statement, locator)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Transforms each statement before passing it on.
+pub struct Map<H, F> {
+    handler: H,
+    f: F,
+}
+
+impl<H, F> Map<H, F> {
+    pub fn into_inner(self) -> H {
+        self.handler
+    }
+}
+
+impl<H: Debug, F> Debug for Map<H, F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Map").field("handler", &self.handler).finish()
+    }
+}
+
+impl<H, F> ParserHandler for Map<H, F>
+where
+    H: ParserHandler,
+    F: FnMut(Statement) -> Statement,
+{
+    fn handle_statement(&mut self, statement: Statement) -> Result<(), String> {
+        let statement = (self.f)(statement);
+        self.handler.handle_statement(statement)
+    }
+
+    fn handle_error(&mut self, error: LogMessage) -> Result<(), String> {
+        self.handler.handle_error(error)
+    }
+
+    fn handle_namespace(&mut self, namespace: Namespace) -> Result<(), String> {
+        self.handler.handle_namespace(namespace)
+    }
+
+    fn handle_located_statement(
+        &mut self,
+        statement: Statement,
+        locator: Locator,
+    ) -> Result<(), String> {
+        let statement = (self.f)(statement);
+        self.handler.handle_located_statement(statement, locator)
+    }
+}
+
+/// Passes on at most a fixed number of statements.
+///
+/// The parse itself continues to the end; later statements are just
+/// dropped. Errors and namespaces are always passed on.
+#[derive(Debug)]
+pub struct Limit<H> {
+    handler: H,
+    remaining: usize,
+}
+
+impl<H> Limit<H> {
+    pub fn into_inner(self) -> H {
+        self.handler
+    }
+
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
+impl<H: ParserHandler> ParserHandler for Limit<H> {
+    fn handle_statement(&mut self, statement: Statement) -> Result<(), String> {
+        if self.remaining == 0 {
+            return Ok(());
+        }
+        self.remaining -= 1;
+        self.handler.handle_statement(statement)
+    }
+
+    fn handle_error(&mut self, error: LogMessage) -> Result<(), String> {
+        self.handler.handle_error(error)
+    }
+
+    fn handle_namespace(&mut self, namespace: Namespace) -> Result<(), String> {
+        self.handler.handle_namespace(namespace)
+    }
+
+    fn handle_located_statement(
+        &mut self,
+        statement: Statement,
+        locator: Locator,
+    ) -> Result<(), String> {
+        if self.remaining == 0 {
+            return Ok(());
+        }
+        self.remaining -= 1;
+        self.handler.handle_located_statement(statement, locator)
+    }
+}
+
+/// Combinators available on every `ParserHandler`.
+///
+/// Handlers are taken by value; pass `&mut handler` to keep hold of
+/// the original.
+pub trait ParserHandlerExt: ParserHandler + Sized {
+    fn tee<B: ParserHandler>(self, second: B) -> Tee<Self, B> {
+        Tee {
+            first: self,
+            second,
+        }
+    }
+
+    fn filter<P: FnMut(&Statement) -> bool>(self, predicate: P) -> Filter<Self, P> {
+        Filter {
+            handler: self,
+            predicate,
+        }
+    }
+
+    fn map<F: FnMut(Statement) -> Statement>(self, f: F) -> Map<Self, F> {
+        Map { handler: self, f }
+    }
+
+    fn limit(self, n: usize) -> Limit<Self> {
+        Limit {
+            handler: self,
+            remaining: n,
+        }
+    }
+}
+
+impl<H: ParserHandler> ParserHandlerExt for H {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use push::MemoryParserHandler;
+    use push::Parser;
+
+    fn parse(handler: &ParserHandler) {
+        let about = include_str!("./test-files/about_two.rdf");
+        let mut p = Parser::new("rdfxml", "http://www.example.com", handler);
+        p.parse_chunk(about);
+        p.parse_complete();
+    }
+
+    #[test]
+    fn fn_handler() {
+        let mut statements = 0;
+        let mut namespaces = vec![];
+        {
+            let h = FnHandler::new()
+                .on_statement(|_| {
+                    statements += 1;
+                    Ok(())
+                })
+                .on_namespace(|ns| {
+                    namespaces.push(ns);
+                    Ok(())
+                });
+            parse(&h);
+        }
+
+        assert_eq!(2, statements);
+        assert!(namespaces
+            .iter()
+            .any(|ns| ns.prefix() == Some("dc")));
+    }
+
+    #[test]
+    fn fn_handler_stops() {
+        let mut statements = 0;
+        {
+            let h = FnHandler::new().on_statement(|_| {
+                statements += 1;
+                Err("Enough".to_string())
+            });
+            parse(&h);
+        }
+
+        assert_eq!(1, statements);
+    }
+
+    #[test]
+    fn tee() {
+        let mut first = MemoryParserHandler::new();
+        let mut second = MemoryParserHandler::new();
+        {
+            let h = (&mut first).tee(&mut second);
+            parse(&h);
+        }
+
+        assert_eq!(2, first.0.len());
+        assert_eq!(2, second.0.len());
+    }
+
+    #[test]
+    fn filter_and_limit() {
+        let mut m = MemoryParserHandler::new();
+        {
+            let h = (&mut m)
+                .filter(|st| match st.subject() {
+                    Term::URI(iri) => iri.as_str() == "http://www.example.com/",
+                    _ => false,
+                })
+                .limit(5);
+            parse(&h);
+        }
+        assert_eq!(1, m.0.len());
+
+        let mut m = MemoryParserHandler::new();
+        {
+            let h = (&mut m).limit(1);
+            parse(&h);
+        }
+        assert_eq!(1, m.0.len());
+    }
+
+    #[test]
+    fn map() {
+        let mut m = MemoryParserHandler::new();
+        {
+            let h = (&mut m).map(|st| {
+                Statement::new(
+                    st.subject().clone(),
+                    st.predicate().clone(),
+                    st.object().clone(),
+                    Some(Term::URI(IRI::new("http://www.example.com/graph"))),
+                )
+            });
+            parse(&h);
+        }
+
+        assert!(m.0.iter().all(|event| match event {
+            Ok(st) => st.graph().is_some(),
+            Err(_) => false,
+        }));
+    }
+}
@@ -0,0 +1,278 @@
+//! `raptor_iostream`s backed by Rust `Read` and `Write` implementations,
+//! so that libraptor can pull from sockets or decompressors, and write
+//! to files or pipes, without the content being copied about first.
+//!
+//! Most code will want `Parser::parse_read` or `Serializer::to_writer`
+//! rather than these.
+use super::*;
+
+use std::io::Read;
+use std::io::Write;
+use std::os::raw::c_int;
+use std::slice;
+
+struct ReadContext<'a> {
+    reader: Box<Read + 'a>,
+    eof: bool,
+    error: Option<io::Error>,
+}
+
+struct WriteContext<'a> {
+    writer: Box<Write + 'a>,
+    error: Option<io::Error>,
+}
+
+// libraptor keeps a pointer to its handler rather than a copy, so
+// these must live as long as any stream.
+static READ_HANDLER: raptor_iostream_handler = raptor_iostream_handler {
+    version: 2,
+    init: None,
+    finish: None,
+    write_byte: None,
+    write_bytes: None,
+    write_end: None,
+    read_bytes: Some(read_bytes),
+    read_eof: Some(read_eof),
+};
+
+static WRITE_HANDLER: raptor_iostream_handler = raptor_iostream_handler {
+    version: 2,
+    init: None,
+    finish: None,
+    write_byte: Some(write_byte),
+    write_bytes: Some(write_bytes),
+    write_end: Some(write_end),
+    read_bytes: None,
+    read_eof: None,
+};
+
+/// A readable `raptor_iostream`.
+pub struct ReadStream<'a> {
+    raw: *mut raptor_iostream,
+    context: Box<ReadContext<'a>>,
+}
+
+impl<'a> ReadStream<'a> {
+    /// Unsafe because `world` must outlive the stream.
+    pub unsafe fn new<R: Read + 'a>(world: *mut raptor_world, reader: R) -> ReadStream<'a> {
+        let mut context = Box::new(ReadContext {
+            reader: Box::new(reader),
+            eof: false,
+            error: None,
+        });
+        let raw = raptor_new_iostream_from_handler(
+            world,
+            &mut *context as *mut ReadContext as *mut c_void,
+            &READ_HANDLER,
+        );
+        ReadStream { raw, context }
+    }
+
+    pub fn as_raw(&self) -> *mut raptor_iostream {
+        self.raw
+    }
+
+    /// The error which ended reading, if there was one. libraptor only
+    /// sees that the stream failed.
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.context.error.take()
+    }
+}
+
+impl<'a> Debug for ReadStream<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ReadStream")
+            .field("raw", &self.raw)
+            .field("eof", &self.context.eof)
+            .finish()
+    }
+}
+
+impl<'a> Drop for ReadStream<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            raptor_free_iostream(self.raw);
+        }
+    }
+}
+
+/// A writable `raptor_iostream`.
+pub struct WriteStream<'a> {
+    raw: *mut raptor_iostream,
+    context: Box<WriteContext<'a>>,
+}
+
+impl<'a> WriteStream<'a> {
+    /// Unsafe because `world` must outlive the stream.
+    pub unsafe fn new<W: Write + 'a>(world: *mut raptor_world, writer: W) -> WriteStream<'a> {
+        let mut context = Box::new(WriteContext {
+            writer: Box::new(writer),
+            error: None,
+        });
+        let raw = raptor_new_iostream_from_handler(
+            world,
+            &mut *context as *mut WriteContext as *mut c_void,
+            &WRITE_HANDLER,
+        );
+        WriteStream { raw, context }
+    }
+
+    pub fn as_raw(&self) -> *mut raptor_iostream {
+        self.raw
+    }
+
+    /// Flush the writer, returning the first error seen while writing.
+    pub fn flush(&mut self) -> io::Result<()> {
+        if let Some(error) = self.context.error.take() {
+            return Err(error);
+        }
+        self.context.writer.flush()
+    }
+}
+
+impl<'a> Debug for WriteStream<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WriteStream")
+            .field("raw", &self.raw)
+            .finish()
+    }
+}
+
+impl<'a> Drop for WriteStream<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            raptor_free_iostream(self.raw);
+        }
+    }
+}
+
+// Reads whole items of `size` bytes, returning how many, or -1 on
+// error. libraptor takes a short read to mean the end of the stream, so
+// this fills the buffer unless the reader is exhausted.
+extern "C" fn read_bytes(
+    context: *mut c_void,
+    ptr: *mut c_void,
+    size: usize,
+    nmemb: usize,
+) -> c_int {
+    let context = unsafe { &mut *(context as *mut ReadContext) };
+    if size == 0 || nmemb == 0 || context.eof {
+        return 0;
+    }
+
+    let buffer = unsafe { slice::from_raw_parts_mut(ptr as *mut u8, size * nmemb) };
+    let mut total = 0;
+    while total < buffer.len() {
+        match context.reader.read(&mut buffer[total..]) {
+            Ok(0) => {
+                context.eof = true;
+                break;
+            }
+            Ok(n) => total += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => {
+                context.error = Some(e);
+                return -1;
+            }
+        }
+    }
+    (total / size) as c_int
+}
+
+extern "C" fn read_eof(context: *mut c_void) -> c_int {
+    let context = unsafe { &*(context as *mut ReadContext) };
+    context.eof as c_int
+}
+
+fn write(context: *mut c_void, bytes: &[u8]) -> bool {
+    let context = unsafe { &mut *(context as *mut WriteContext) };
+    if context.error.is_some() {
+        return false;
+    }
+    match context.writer.write_all(bytes) {
+        Ok(()) => true,
+        Err(e) => {
+            context.error = Some(e);
+            false
+        }
+    }
+}
+
+extern "C" fn write_byte(context: *mut c_void, byte: c_int) -> c_int {
+    if write(context, &[byte as u8]) {
+        0
+    } else {
+        1
+    }
+}
+
+extern "C" fn write_bytes(
+    context: *mut c_void,
+    ptr: *const c_void,
+    size: usize,
+    nmemb: usize,
+) -> c_int {
+    let bytes = unsafe { slice::from_raw_parts(ptr as *const u8, size * nmemb) };
+    if write(context, bytes) {
+        nmemb as c_int
+    } else {
+        -1
+    }
+}
+
+extern "C" fn write_end(context: *mut c_void) -> c_int {
+    let context = unsafe { &mut *(context as *mut WriteContext) };
+    match context.writer.flush() {
+        Ok(()) => 0,
+        Err(e) => {
+            context.error.get_or_insert(e);
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_stream() {
+        unsafe {
+            let world = raptor_new_world();
+            {
+                let content: &[u8] = b"Hello, world";
+                let mut stream = ReadStream::new(world, content);
+                let mut buffer = [0u8; 5];
+
+                let n = raptor_iostream_read_bytes(
+                    buffer.as_mut_ptr() as *mut c_void,
+                    1,
+                    5,
+                    stream.as_raw(),
+                );
+                assert_eq!(5, n);
+                assert_eq!(b"Hello", &buffer);
+                assert_eq!(0, raptor_iostream_read_eof(stream.as_raw()));
+                assert!(stream.take_error().is_none());
+            }
+            raptor_free_world(world);
+        }
+    }
+
+    #[test]
+    fn write_stream() {
+        let mut output = vec![];
+        unsafe {
+            let world = raptor_new_world();
+            {
+                let mut stream = WriteStream::new(world, &mut output);
+                let hello = CString::new("Hello").unwrap();
+                raptor_iostream_string_write(hello.as_ptr() as *const c_void, stream.as_raw());
+                raptor_iostream_write_byte(b'!' as c_int, stream.as_raw());
+                stream.flush().unwrap();
+            }
+            raptor_free_world(world);
+        }
+        assert_eq!(b"Hello!".to_vec(), output);
+    }
+}
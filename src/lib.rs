@@ -1,16 +1,60 @@
+#[cfg(feature = "bz2")]
+extern crate bzip2;
+#[cfg(feature = "gz")]
+extern crate flate2;
+#[cfg(feature = "async")]
+extern crate futures;
 extern crate libraptor_sys;
+#[cfg(feature = "log")]
+#[macro_use(log)]
+extern crate log;
+extern crate regex;
+#[macro_use]
+extern crate serde_json;
+extern crate sha2;
+#[cfg(feature = "tracing")]
+#[macro_use(event)]
+extern crate tracing;
+#[cfg(feature = "xz")]
+extern crate xz2;
 
+pub mod canon;
+pub mod channel;
+pub mod compression;
+pub mod conneg;
+pub mod graph;
+pub mod grep;
+pub mod handler;
+pub mod iostream;
+pub mod lint;
+#[cfg(any(feature = "log", feature = "tracing"))]
+pub mod logging;
+pub mod namespaces;
+pub mod parallel;
+pub mod patch;
 pub mod pull;
 pub mod push;
+pub mod serializer;
+pub mod stats;
+#[cfg(feature = "async")]
+pub mod stream;
+pub mod vocab;
+#[cfg(test)]
+mod w3c;
 
 use libraptor_sys::*;
+use namespaces::Namespaces;
 use std::ffi::CString;
+use std::fmt;
 use std::fmt::Debug;
+use std::fs;
+use std::io;
 use std::mem;
 use std::os::raw::c_char;
 use std::os::raw::c_void;
+use std::path::Path;
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Statement {
     subject: Term,
     predicate: Term,
@@ -18,24 +62,232 @@ pub struct Statement {
     graph: Option<Term>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Literal {
     value: String,
     datatype: Option<IRI>,
     lang: Option<String>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct IRI(String);
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Term {
     URI(IRI),
     Literal(Literal),
     Blank(String),
 }
 
-#[derive(Debug)]
+impl Statement {
+    pub fn new(subject: Term, predicate: Term, object: Term, graph: Option<Term>) -> Statement {
+        Statement {
+            subject,
+            predicate,
+            object,
+            graph,
+        }
+    }
+
+    pub fn subject(&self) -> &Term {
+        &self.subject
+    }
+
+    pub fn predicate(&self) -> &Term {
+        &self.predicate
+    }
+
+    pub fn object(&self) -> &Term {
+        &self.object
+    }
+
+    pub fn graph(&self) -> Option<&Term> {
+        self.graph.as_ref()
+    }
+
+    /// The statement in Turtle, as formatted by libraptor; see
+    /// `Term::to_turtle`. Any graph is written after the object, as in
+    /// N-Quads.
+    pub fn to_turtle(&self, namespaces: &Namespaces, base: Option<&IRI>) -> String {
+        let mut terms = vec![&self.subject, &self.predicate, &self.object];
+        if let Some(ref graph) = self.graph {
+            terms.push(graph);
+        }
+        let mut turtle = rust_terms_to_turtle(&terms, namespaces, base).join(" ");
+        turtle.push_str(" .");
+        turtle
+    }
+}
+
+impl Literal {
+    pub fn new(value: &str, datatype: Option<IRI>, lang: Option<&str>) -> Literal {
+        Literal {
+            value: value.to_string(),
+            datatype,
+            lang: lang.map(|l| l.to_string()),
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    pub fn datatype(&self) -> Option<&IRI> {
+        self.datatype.as_ref()
+    }
+
+    pub fn lang(&self) -> Option<&str> {
+        self.lang.as_ref().map(|l| l.as_str())
+    }
+}
+
+impl IRI {
+    pub fn new(iri: &str) -> IRI {
+        IRI(iri.to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The `file:` IRI of `path`, made absolute, as a base for parsing
+    /// the file. Bytes which cannot appear in the path of an IRI as
+    /// they are, such as spaces, `#` and anything outside ASCII, are
+    /// percent-encoded.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<IRI> {
+        let path = fs::canonicalize(path)?;
+        let mut iri = String::from("file://");
+        for &b in path_bytes(&path).iter() {
+            match b {
+                b'A'..=b'Z'
+                | b'a'..=b'z'
+                | b'0'..=b'9'
+                | b'-'
+                | b'.'
+                | b'_'
+                | b'~'
+                | b'/'
+                | b'!'
+                | b'$'
+                | b'&'
+                | b'\''
+                | b'('
+                | b')'
+                | b'*'
+                | b'+'
+                | b','
+                | b';'
+                | b'='
+                | b':'
+                | b'@' => iri.push(b as char),
+                b => iri.push_str(&format!("%{:02X}", b)),
+            }
+        }
+        Ok(IRI(iri))
+    }
+}
+
+// The bytes of a path, which on Unix need not be UTF-8.
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> Vec<u8> {
+    path.to_string_lossy().replace('\\', "/").into_bytes()
+}
+
+impl Term {
+    /// The term in Turtle, as formatted by libraptor: IRIs are written
+    /// as prefixed names where `namespaces` allows, or else relative to
+    /// `base`.
+    pub fn to_turtle(&self, namespaces: &Namespaces, base: Option<&IRI>) -> String {
+        rust_terms_to_turtle(&[self], namespaces, base).remove(0)
+    }
+
+    /// The term in N-Triples, as formatted by libraptor. Terms which
+    /// libraptor cannot represent are written as by `Display`.
+    pub fn to_ntriples(&self) -> String {
+        with_formatting_world(|world| unsafe {
+            match rust_term_to_raptor_term(world, self) {
+                Ok(term) => {
+                    let ntriples = raptor_owned_string_to_rust_string(raptor_term_to_string(term));
+                    raptor_free_term(term);
+                    ntriples
+                }
+                Err(_) => self.to_string(),
+            }
+        })
+    }
+}
+
+const XSD_STRING: &str = "http://www.w3.org/2001/XMLSchema#string";
+
+// Escape a literal value as canonical N-Triples does: the short forms
+// where there are some, and \uXXXX for remaining control characters.
+fn escape_literal(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\u{8}' => escaped.push_str("\\b"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\u{c}' => escaped.push_str("\\f"),
+            '\r' => escaped.push_str("\\r"),
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\u{0}'..='\u{1f}' | '\u{7f}' => {
+                escaped.push_str(&format!("\\u{:04X}", c as u32))
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+impl fmt::Display for IRI {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Terms display as they would be written in N-Triples.
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Term::URI(iri) => write!(f, "<{}>", iri.0),
+            Term::Blank(label) => write!(f, "_:{}", label),
+            Term::Literal(literal) => {
+                write!(f, "\"{}\"", escape_literal(&literal.value))?;
+                if let Some(ref lang) = literal.lang {
+                    write!(f, "@{}", lang)
+                } else {
+                    match literal.datatype {
+                        Some(ref datatype) if datatype.0 != XSD_STRING => {
+                            write!(f, "^^<{}>", datatype.0)
+                        }
+                        _ => Ok(()),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Statements display as a line of N-Quads, without the line ending.
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {}", self.subject, self.predicate, self.object)?;
+        if let Some(ref graph) = self.graph {
+            write!(f, " {}", graph)?;
+        }
+        write!(f, " .")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum LogLevel {
     None,
     Trace,
@@ -46,7 +298,22 @@ pub enum LogLevel {
     Fatal,
 }
 
-#[derive(Debug)]
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            LogLevel::None => "none",
+            LogLevel::Trace => "trace",
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Warn => "warning",
+            LogLevel::Error => "error",
+            LogLevel::Fatal => "fatal",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct Locator {
     iri: Option<IRI>,
     file: Option<String>,
@@ -55,10 +322,273 @@ pub struct Locator {
     byte: Option<i32>,
 }
 
-#[derive(Debug)]
+impl Locator {
+    pub fn iri(&self) -> Option<&IRI> {
+        self.iri.as_ref()
+    }
+
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_ref().map(|f| f.as_str())
+    }
+
+    pub fn line(&self) -> Option<i32> {
+        self.line
+    }
+
+    pub fn column(&self) -> Option<i32> {
+        self.column
+    }
+
+    pub fn byte(&self) -> Option<i32> {
+        self.byte
+    }
+}
+
+/// Locators display in the usual `file:line:column` form, leaving
+/// out whatever is not known.
+impl fmt::Display for Locator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.file, &self.iri) {
+            (Some(file), _) => write!(f, "{}", file)?,
+            (None, Some(iri)) => write!(f, "{}", iri)?,
+            (None, None) => write!(f, "-")?,
+        }
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+            if let Some(column) = self.column {
+                write!(f, ":{}", column)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct LogMessage {
     text: String,
     level: LogLevel,
+    domain: Option<String>,
+    locator: Option<Locator>,
+}
+
+impl fmt::Display for LogMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.level, self.text)
+    }
+}
+
+impl LogMessage {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn level(&self) -> LogLevel {
+        self.level
+    }
+
+    /// The part of libraptor the message came from, such as `parser`
+    /// or `iostream`.
+    pub fn domain(&self) -> Option<&str> {
+        self.domain.as_ref().map(|d| d.as_str())
+    }
+
+    pub fn locator(&self) -> Option<&Locator> {
+        self.locator.as_ref()
+    }
+}
+
+/// The RDF syntaxes that libraptor can parse or serialize.
+///
+/// Not every syntax can be both parsed and serialized; see the
+/// libraptor documentation for which is which.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Syntax {
+    RdfXml,
+    RdfXmlAbbrev,
+    RdfXmlXmp,
+    Turtle,
+    NTriples,
+    NQuads,
+    TriG,
+    Json,
+    JsonTriples,
+    Rss10,
+    Atom,
+    Dot,
+    Html,
+    RdfA,
+    Grddl,
+    RssTagSoup,
+    Guess,
+}
+
+const SYNTAXES: &[Syntax] = &[
+    Syntax::RdfXml,
+    Syntax::RdfXmlAbbrev,
+    Syntax::RdfXmlXmp,
+    Syntax::Turtle,
+    Syntax::NTriples,
+    Syntax::NQuads,
+    Syntax::TriG,
+    Syntax::Json,
+    Syntax::JsonTriples,
+    Syntax::Rss10,
+    Syntax::Atom,
+    Syntax::Dot,
+    Syntax::Html,
+    Syntax::RdfA,
+    Syntax::Grddl,
+    Syntax::RssTagSoup,
+    Syntax::Guess,
+];
+
+impl Syntax {
+    pub fn all() -> &'static [Syntax] {
+        SYNTAXES
+    }
+
+    /// The name libraptor knows this syntax by.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Syntax::RdfXml => "rdfxml",
+            Syntax::RdfXmlAbbrev => "rdfxml-abbrev",
+            Syntax::RdfXmlXmp => "rdfxml-xmp",
+            Syntax::Turtle => "turtle",
+            Syntax::NTriples => "ntriples",
+            Syntax::NQuads => "nquads",
+            Syntax::TriG => "trig",
+            Syntax::Json => "json",
+            Syntax::JsonTriples => "json-triples",
+            Syntax::Rss10 => "rss-1.0",
+            Syntax::Atom => "atom",
+            Syntax::Dot => "dot",
+            Syntax::Html => "html",
+            Syntax::RdfA => "rdfa",
+            Syntax::Grddl => "grddl",
+            Syntax::RssTagSoup => "rss-tag-soup",
+            Syntax::Guess => "guess",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Syntax> {
+        SYNTAXES.iter().find(|s| s.name() == name).cloned()
+    }
+
+    /// The syntax usually meant by a file name, looking past any
+    /// compression extension, as in `dump.nt.gz`.
+    pub fn from_file_name(name: &str) -> Option<Syntax> {
+        compression::syntax_extension(name).and_then(Syntax::from_extension)
+    }
+
+    /// The syntax usually meant by a file extension.
+    pub fn from_extension(extension: &str) -> Option<Syntax> {
+        match extension.to_lowercase().as_str() {
+            "rdf" | "owl" | "xml" => Some(Syntax::RdfXml),
+            "ttl" => Some(Syntax::Turtle),
+            "nt" => Some(Syntax::NTriples),
+            "nq" => Some(Syntax::NQuads),
+            "trig" => Some(Syntax::TriG),
+            "json" => Some(Syntax::Json),
+            "rss" => Some(Syntax::RssTagSoup),
+            "html" | "xhtml" => Some(Syntax::RdfA),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Syntax {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// What libraptor says about one of its parsers or serializers.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyntaxDescription {
+    names: Vec<String>,
+    label: String,
+    mime_types: Vec<(String, u8)>,
+    uris: Vec<String>,
+}
+
+impl SyntaxDescription {
+    /// The names of the syntax, the first being the usual one.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn name(&self) -> &str {
+        &self.names[0]
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    /// MIME types with their quality, from 0 to 10.
+    pub fn mime_types(&self) -> &[(String, u8)] {
+        &self.mime_types
+    }
+
+    /// IRIs identifying the syntax, such as its specification.
+    pub fn uris(&self) -> &[String] {
+        &self.uris
+    }
+
+    /// The `Syntax` of this description, if it is one we know.
+    pub fn syntax(&self) -> Option<Syntax> {
+        Syntax::from_name(self.name())
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Parse(LogMessage),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Parse(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+/// A namespace declared in the document being parsed.
+///
+/// The prefix is absent for the default namespace, and the IRI is
+/// absent when a declaration is undone.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Namespace {
+    prefix: Option<String>,
+    iri: Option<IRI>,
+}
+
+impl Namespace {
+    pub fn new(prefix: Option<&str>, iri: Option<IRI>) -> Namespace {
+        Namespace {
+            prefix: prefix.map(|p| p.to_string()),
+            iri,
+        }
+    }
+
+    pub fn prefix(&self) -> Option<&str> {
+        self.prefix.as_ref().map(|p| p.as_str())
+    }
+
+    pub fn iri(&self) -> Option<&IRI> {
+        self.iri.as_ref()
+    }
 }
 
 // libraptor strings are not normal strings -- they are Unicode
@@ -86,7 +616,28 @@ fn raptor_string_to_rust_string_maybe(s: *const c_char) -> Option<String> {
     }
 }
 
-#[allow(dead_code)]
+unsafe fn raptor_syntax_description_to_rust(
+    description: *const raptor_syntax_description,
+) -> SyntaxDescription {
+    let d = &*description;
+    let strings = |ptr: *const *const c_char, count: u32| -> Vec<String> {
+        (0..count as isize)
+            .map(|i| raptor_string_to_rust_string(*ptr.offset(i)))
+            .collect()
+    };
+    SyntaxDescription {
+        names: strings(d.names, d.names_count),
+        label: raptor_string_to_rust_string(d.label),
+        mime_types: (0..d.mime_types_count as isize)
+            .map(|i| {
+                let t = &*d.mime_types.offset(i);
+                (raptor_string_to_rust_string(t.mime_type), t.q)
+            })
+            .collect(),
+        uris: strings(d.uri_strings, d.uri_strings_count),
+    }
+}
+
 fn raptor_locator_to_rust_locator(locator: *mut raptor_locator) -> Locator {
     unsafe {
         Locator {
@@ -130,6 +681,30 @@ fn raptor_log_message_to_rust_log_message(message: *mut raptor_log_message) -> L
                 raptor_log_level_RAPTOR_LOG_LEVEL_FATAL => LogLevel::Fatal,
                 _ => panic!("Unknown log level"),
             },
+            domain: raptor_string_to_rust_string_maybe(raptor_domain_get_label(
+                (*message).domain,
+            )),
+            locator: if (*message).locator.is_null() {
+                None
+            } else {
+                Some(raptor_locator_to_rust_locator((*message).locator))
+            },
+        }
+    }
+}
+
+fn raptor_namespace_to_rust_namespace(namespace: *mut raptor_namespace) -> Namespace {
+    unsafe {
+        let uri = raptor_namespace_get_uri(namespace);
+        Namespace {
+            prefix: raptor_string_to_rust_string_maybe(
+                raptor_namespace_get_prefix(namespace) as *const c_char,
+            ),
+            iri: if uri.is_null() {
+                None
+            } else {
+                Some(raptor_uri_to_rust_iri(uri))
+            },
         }
     }
 }
@@ -225,10 +800,285 @@ fn raptor_literal_to_rust_literal(literal: raptor_term_literal_value) -> Literal
     }
 }
 
+fn rust_iri_to_raptor_uri(world: *mut raptor_world, iri: &IRI) -> *mut raptor_uri {
+    let iri = CString::new(iri.0.as_str()).unwrap();
+    unsafe { raptor_new_uri(world, iri.as_ptr() as *const u8) }
+}
+
+// The returned term is owned by the caller.
+fn rust_term_to_raptor_term(
+    world: *mut raptor_world,
+    term: &Term,
+) -> Result<*mut raptor_term, String> {
+    let raw = unsafe {
+        match term {
+            Term::URI(iri) => {
+                let uri = rust_iri_to_raptor_uri(world, iri);
+                // The term takes its own reference to the URI.
+                let term = raptor_new_term_from_uri(world, uri);
+                raptor_free_uri(uri);
+                term
+            }
+            Term::Literal(literal) => {
+                let datatype = match literal.datatype {
+                    Some(ref datatype) => rust_iri_to_raptor_uri(world, datatype),
+                    None => std::ptr::null_mut(),
+                };
+                // libraptor counts the length of tags in a byte.
+                let (lang, lang_len) = match literal.lang {
+                    Some(ref lang) if lang.len() > u8::MAX as usize => {
+                        if !datatype.is_null() {
+                            raptor_free_uri(datatype);
+                        }
+                        return Err(format!("Language tag is too long: {}", lang));
+                    }
+                    Some(ref lang) => (lang.as_ptr(), lang.len() as u8),
+                    None => (std::ptr::null(), 0),
+                };
+                let term = raptor_new_term_from_counted_literal(
+                    world,
+                    literal.value.as_ptr(),
+                    literal.value.len(),
+                    datatype,
+                    lang,
+                    lang_len,
+                );
+                if !datatype.is_null() {
+                    raptor_free_uri(datatype);
+                }
+                term
+            }
+            Term::Blank(label) => {
+                raptor_new_term_from_counted_blank(world, label.as_ptr(), label.len())
+            }
+        }
+    };
+    // As for a literal with both a datatype and a language.
+    if raw.is_null() {
+        Err(format!("libraptor cannot represent {}", term))
+    } else {
+        Ok(raw)
+    }
+}
+
+// Take a string allocated by libraptor, freeing it.
+unsafe fn raptor_owned_string_to_rust_string(s: *mut u8) -> String {
+    if s.is_null() {
+        return String::new();
+    }
+    let string = raptor_string_to_rust_string(s as *const c_char);
+    raptor_free_memory(s as *mut c_void);
+    string
+}
+
+// A world for formatting terms, which is comparatively expensive to set
+// up for each one, so is kept for the life of the thread.
+struct FormattingWorld(*mut raptor_world);
+
+impl Drop for FormattingWorld {
+    fn drop(&mut self) {
+        unsafe { raptor_free_world(self.0) }
+    }
+}
+
+thread_local! {
+    static FORMATTING_WORLD: FormattingWorld = unsafe {
+        let world = raptor_new_world();
+        #[cfg(any(feature = "log", feature = "tracing"))]
+        logging::install(world);
+        FormattingWorld(world)
+    };
+}
+
+fn with_formatting_world<T, F: FnOnce(*mut raptor_world) -> T>(f: F) -> T {
+    FORMATTING_WORLD.with(|world| f(world.0))
+}
+
+// Several terms sharing one namespace stack.
+fn rust_terms_to_turtle(
+    terms: &[&Term],
+    namespaces: &Namespaces,
+    base: Option<&IRI>,
+) -> Vec<String> {
+    with_formatting_world(|world| unsafe {
+        let nstack = raptor_new_namespaces(world, 0);
+        for (prefix, iri) in namespaces.iter() {
+            let c_prefix = CString::new(prefix).unwrap();
+            let c_iri = CString::new(iri.as_str()).unwrap();
+            raptor_namespaces_start_namespace_full(
+                nstack,
+                if prefix.is_empty() {
+                    std::ptr::null()
+                } else {
+                    c_prefix.as_ptr() as *const u8
+                },
+                c_iri.as_ptr() as *const u8,
+                0,
+            );
+        }
+        let base = match base {
+            Some(base) => rust_iri_to_raptor_uri(world, base),
+            None => std::ptr::null_mut(),
+        };
+
+        let turtle = terms
+            .iter()
+            .map(|term| {
+                let raw = match rust_term_to_raptor_term(world, term) {
+                    Ok(raw) => raw,
+                    Err(_) => return term.to_string(),
+                };
+                let turtle = raptor_term_to_turtle_string(raw, nstack, base);
+                let turtle = raptor_owned_string_to_rust_string(turtle);
+                raptor_free_term(raw);
+                turtle
+            })
+            .collect();
+
+        if !base.is_null() {
+            raptor_free_uri(base);
+        }
+        raptor_free_namespaces(nstack);
+        turtle
+    })
+}
+
+// The returned statement is owned by the caller.
+fn rust_statement_to_raptor_statement(
+    world: *mut raptor_world,
+    statement: &Statement,
+) -> Result<*mut raptor_statement, String> {
+    let mut terms = vec![&statement.subject, &statement.predicate, &statement.object];
+    terms.extend(statement.graph.as_ref());
+
+    let mut raw_terms = vec![];
+    for term in terms {
+        match rust_term_to_raptor_term(world, term) {
+            Ok(raw) => raw_terms.push(raw),
+            Err(e) => {
+                for raw in raw_terms {
+                    unsafe { raptor_free_term(raw) };
+                }
+                return Err(e);
+            }
+        }
+    }
+    raw_terms.resize(4, std::ptr::null_mut());
+
+    // The statement owns the terms from here, even if it fails.
+    let raw = unsafe {
+        raptor_new_statement_from_nodes(
+            world,
+            raw_terms[0],
+            raw_terms[1],
+            raw_terms[2],
+            raw_terms[3],
+        )
+    };
+    if raw.is_null() {
+        Err(format!("libraptor cannot represent {}", statement))
+    } else {
+        Ok(raw)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn display_statement() {
+        let statement = Statement::new(
+            Term::URI(IRI::new("http://www.example.com/s")),
+            Term::URI(IRI::new("http://www.example.com/p")),
+            Term::Literal(Literal::new("say \"hi\"\n\u{1}", None, Some("en"))),
+            Some(Term::Blank("g".to_string())),
+        );
+
+        assert_eq!(
+            statement.to_string(),
+            "<http://www.example.com/s> <http://www.example.com/p> \"say \\\"hi\\\"\\n\\u0001\"@en _:g ."
+        );
+    }
+
+    #[test]
+    fn to_turtle() {
+        let mut namespaces = Namespaces::new();
+        namespaces.insert("ex", IRI::new("http://www.example.com/"));
+        let base = IRI::new("http://www.example.org/");
+
+        let statement = Statement::new(
+            Term::URI(IRI::new("http://www.example.com/s")),
+            Term::URI(IRI::new("http://www.example.org/p")),
+            Term::Literal(Literal::new("chat", None, Some("fr"))),
+            None,
+        );
+        assert_eq!(
+            "ex:s <p> \"chat\"@fr .",
+            statement.to_turtle(&namespaces, Some(&base))
+        );
+        assert_eq!(
+            "<http://www.example.com/s>",
+            statement.subject().to_ntriples()
+        );
+    }
+
+    #[test]
+    fn round_trip_statement() {
+        let statement = Statement::new(
+            Term::Blank("b1".to_string()),
+            Term::URI(IRI::new("http://www.example.com/p")),
+            Term::Literal(Literal::new(
+                "1",
+                Some(IRI::new("http://www.w3.org/2001/XMLSchema#integer")),
+                None,
+            )),
+            Some(Term::URI(IRI::new("http://www.example.com/g"))),
+        );
+
+        unsafe {
+            let world = raptor_new_world();
+            let raw = rust_statement_to_raptor_statement(world, &statement).unwrap();
+            assert_eq!(statement, raptor_statement_to_rust_statement(raw));
+            raptor_free_statement(raw);
+            raptor_free_world(world);
+        }
+    }
+
+    #[test]
+    fn unrepresentable_terms() {
+        let both = Term::Literal(Literal::new(
+            "1",
+            Some(IRI::new("http://www.w3.org/2001/XMLSchema#integer")),
+            Some("en"),
+        ));
+        let long_lang = Term::Literal(Literal::new("1", None, Some(&"a".repeat(256))));
+        let s = Term::URI(IRI::new("http://www.example.com/s"));
+
+        unsafe {
+            let world = raptor_new_world();
+            for object in vec![both, long_lang] {
+                let statement = Statement::new(s.clone(), s.clone(), object, None);
+                assert!(rust_statement_to_raptor_statement(world, &statement).is_err());
+            }
+            raptor_free_world(world);
+        }
+    }
+
+    #[test]
+    fn iri_from_path() {
+        let dir = std::env::temp_dir().join(format!("raptor-iri-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("a b#c.ttl");
+        fs::write(&path, "").unwrap();
+        let iri = IRI::from_path(&path);
+        fs::remove_dir_all(&dir).unwrap();
+
+        let iri = iri.unwrap();
+        assert!(iri.as_str().starts_with("file:///"));
+        assert!(iri.as_str().ends_with("/a%20b%23c.ttl"));
+    }
+
     #[test]
     fn raw_new_free_world() {
         unsafe {
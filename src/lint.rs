@@ -0,0 +1,229 @@
+//! Checking RDF files for problems without keeping their content.
+//!
+//! Every log message from a parse becomes a `Diagnostic`, which can be
+//! reported for people, as JSON, or as SARIF for editors and code
+//! review tools.
+use super::handler::FnHandler;
+use super::push::Parser;
+use super::*;
+
+use serde_json::Value;
+
+use std::path::Path;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    file: String,
+    line: Option<i32>,
+    column: Option<i32>,
+    severity: LogLevel,
+    message: String,
+}
+
+impl Diagnostic {
+    pub fn from_message(file: &str, message: &LogMessage) -> Diagnostic {
+        Diagnostic {
+            file: file.to_string(),
+            line: message.locator.as_ref().and_then(|l| l.line),
+            column: message.locator.as_ref().and_then(|l| l.column),
+            severity: message.level,
+            message: message.text.clone(),
+        }
+    }
+
+    /// An error about the file as a whole, such as it being unreadable.
+    pub fn error(file: &str, message: &str) -> Diagnostic {
+        Diagnostic {
+            file: file.to_string(),
+            line: None,
+            column: None,
+            severity: LogLevel::Error,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    pub fn line(&self) -> Option<i32> {
+        self.line
+    }
+
+    pub fn column(&self) -> Option<i32> {
+        self.column
+    }
+
+    pub fn severity(&self) -> LogLevel {
+        self.severity
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity >= LogLevel::Error
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "file": self.file,
+            "line": self.line,
+            "column": self.column,
+            "severity": self.severity.to_string(),
+            "message": self.message,
+        })
+    }
+
+    fn sarif_level(&self) -> &'static str {
+        match self.severity {
+            LogLevel::Error | LogLevel::Fatal => "error",
+            LogLevel::Warn => "warning",
+            _ => "note",
+        }
+    }
+
+    fn to_sarif(&self) -> Value {
+        let mut region = serde_json::Map::new();
+        if let Some(line) = self.line {
+            region.insert("startLine".to_string(), json!(line));
+        }
+        if let Some(column) = self.column {
+            region.insert("startColumn".to_string(), json!(column));
+        }
+
+        // SARIF does not allow an empty region.
+        let mut location = json!({ "artifactLocation": { "uri": self.file } });
+        if !region.is_empty() {
+            location["region"] = Value::Object(region);
+        }
+
+        json!({
+            "level": self.sarif_level(),
+            "message": { "text": self.message },
+            "locations": [{ "physicalLocation": location }],
+        })
+    }
+}
+
+/// Diagnostics display as `file:line:column: severity: message`.
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.file)?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+            if let Some(column) = self.column {
+                write!(f, ":{}", column)?;
+            }
+        }
+        write!(f, ": {}: {}", self.severity, self.message)
+    }
+}
+
+/// Parse content, returning a diagnostic for each log message.
+pub fn lint_str(content: &str, syntax: Syntax, baseuri: &str, file: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    {
+        let h = FnHandler::new().on_error(|message| {
+            diagnostics.push(Diagnostic::from_message(file, &message));
+            Ok(())
+        });
+        let mut p = Parser::new(syntax.name(), baseuri, &h);
+        p.parse_chunk(content);
+        p.parse_complete();
+    }
+    diagnostics
+}
+
+/// Parse a file, returning a diagnostic for each log message.
+pub fn lint_file(path: &Path, syntax: Syntax) -> io::Result<Vec<Diagnostic>> {
+    let baseuri = IRI::from_path(path)?.to_string();
+    let file = path.display().to_string();
+
+    let mut diagnostics = vec![];
+    {
+        let h = FnHandler::new().on_error(|message| {
+            diagnostics.push(Diagnostic::from_message(&file, &message));
+            Ok(())
+        });
+        let mut p = Parser::new(syntax.name(), &baseuri, &h);
+        p.parse_file(path)?;
+    }
+    Ok(diagnostics)
+}
+
+pub fn to_json(diagnostics: &[Diagnostic]) -> Value {
+    Value::Array(diagnostics.iter().map(|d| d.to_json()).collect())
+}
+
+/// A SARIF 2.1.0 log with a single run.
+pub fn to_sarif(diagnostics: &[Diagnostic]) -> Value {
+    let results: Vec<Value> = diagnostics.iter().map(|d| d.to_sarif()).collect();
+    json!({
+        "version": "2.1.0",
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "raptor-lint",
+                    "informationUri": "https://github.com/phillord/raptor-rs",
+                }
+            },
+            "results": results,
+        }],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn broken() -> Vec<Diagnostic> {
+        let broken = "<http://www.example.com/s> <http://www.example.com/p> .\n";
+        lint_str(broken, Syntax::NTriples, "http://www.example.com", "broken.nt")
+    }
+
+    #[test]
+    fn clean() {
+        let about = include_str!("./test-files/about.rdf");
+        let diagnostics = lint_str(about, Syntax::RdfXml, "http://www.example.com", "about.rdf");
+        assert!(diagnostics.is_empty());
+    }
+
+    #[test]
+    fn diagnostics() {
+        let diagnostics = broken();
+        assert!(diagnostics.iter().any(|d| d.is_error()));
+        assert!(diagnostics
+            .iter()
+            .all(|d| d.to_string().starts_with("broken.nt:")));
+    }
+
+    #[test]
+    fn sarif() {
+        let sarif = to_sarif(&broken());
+        assert_eq!("2.1.0", sarif["version"]);
+
+        let result = &sarif["runs"][0]["results"][0];
+        assert_eq!("error", result["level"]);
+        assert_eq!(
+            "broken.nt",
+            result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"]
+        );
+    }
+
+    #[test]
+    fn sarif_without_region() {
+        let sarif = to_sarif(&[Diagnostic::error("missing.nt", "No such file")]);
+        let location = &sarif["runs"][0]["results"][0]["locations"][0]["physicalLocation"];
+        assert_eq!("missing.nt", location["artifactLocation"]["uri"]);
+        assert!(location.get("region").is_none());
+    }
+
+    #[test]
+    fn json() {
+        let json = to_json(&broken());
+        assert_eq!("broken.nt", json[0]["file"]);
+    }
+}
@@ -0,0 +1,184 @@
+//! Routing of libraptor's log messages into the `log` and `tracing`
+//! crates, with the `log` and `tracing` features.
+//!
+//! Parsers send every message here as well as to their
+//! `ParserHandler`; serializers and the other worlds this crate creates,
+//! which have no handler, send them only here.
+//!
+//! With `log`, the target is `raptor::` and the domain of the message,
+//! such as `raptor::parser`, and any location prefixes the text. With
+//! `tracing`, whose targets are fixed, the target is `raptor` and the
+//! domain and location are fields of the event.
+use super::*;
+
+/// Send `message` to whichever of `log` and `tracing` are enabled.
+pub fn emit(message: &LogMessage) {
+    #[cfg(feature = "log")]
+    emit_log(message);
+    #[cfg(feature = "tracing")]
+    emit_tracing(message);
+}
+
+/// Send the messages of `world` to `emit`, rather than to stderr.
+///
+/// Unsafe because `world` must be a live world.
+pub unsafe fn install(world: *mut raptor_world) {
+    raptor_world_set_log_handler(world, std::ptr::null_mut(), Some(world_log_handler));
+}
+
+extern "C" fn world_log_handler(_: *mut c_void, message: *mut raptor_log_message) {
+    emit(&raptor_log_message_to_rust_log_message(message));
+}
+
+#[cfg(feature = "log")]
+fn log_level(level: LogLevel) -> Option<::log::Level> {
+    match level {
+        LogLevel::None => None,
+        LogLevel::Trace => Some(::log::Level::Trace),
+        LogLevel::Debug => Some(::log::Level::Debug),
+        LogLevel::Info => Some(::log::Level::Info),
+        LogLevel::Warn => Some(::log::Level::Warn),
+        LogLevel::Error | LogLevel::Fatal => Some(::log::Level::Error),
+    }
+}
+
+#[cfg(feature = "log")]
+fn emit_log(message: &LogMessage) {
+    let level = match log_level(message.level()) {
+        Some(level) => level,
+        None => return,
+    };
+    let target = format!("raptor::{}", message.domain().unwrap_or("world"));
+
+    match message.locator() {
+        Some(locator) => {
+            let place = locator
+                .file()
+                .map(|f| f.to_string())
+                .or_else(|| locator.iri().map(|iri| iri.to_string()))
+                .unwrap_or_default();
+            match locator.line() {
+                Some(line) => {
+                    log!(target: &target, level, "{}:{}: {}", place, line, message.text())
+                }
+                None => log!(target: &target, level, "{}: {}", place, message.text()),
+            }
+        }
+        None => log!(target: &target, level, "{}", message.text()),
+    }
+}
+
+// tracing needs the level of each event to be known where it is
+// written, hence one event per level.
+#[cfg(feature = "tracing")]
+fn emit_tracing(message: &LogMessage) {
+    let domain = message.domain().unwrap_or("world");
+    let locator = message.locator();
+    let file = locator.and_then(|l| l.file());
+    let uri = locator.and_then(|l| l.iri()).map(|iri| iri.to_string());
+    let uri = uri.as_ref().map(|u| u.as_str());
+    let line = locator.and_then(|l| l.line());
+    let column = locator.and_then(|l| l.column());
+    let byte = locator.and_then(|l| l.byte());
+
+    macro_rules! event_at {
+        ($level:expr) => {
+            event!(
+                target: "raptor",
+                $level,
+                domain,
+                file,
+                uri,
+                line,
+                column,
+                byte,
+                "{}",
+                message.text()
+            )
+        };
+    }
+
+    match message.level() {
+        LogLevel::None => {}
+        LogLevel::Trace => event_at!(::tracing::Level::TRACE),
+        LogLevel::Debug => event_at!(::tracing::Level::DEBUG),
+        LogLevel::Info => event_at!(::tracing::Level::INFO),
+        LogLevel::Warn => event_at!(::tracing::Level::WARN),
+        LogLevel::Error | LogLevel::Fatal => event_at!(::tracing::Level::ERROR),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn levels() {
+        assert_eq!(None, log_level(LogLevel::None));
+        assert_eq!(Some(::log::Level::Warn), log_level(LogLevel::Warn));
+        assert_eq!(Some(::log::Level::Error), log_level(LogLevel::Fatal));
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn parser_messages() {
+        use push::MemoryParserHandler;
+        use push::Parser;
+        use std::cell::RefCell;
+
+        // Records are kept per thread, so that other tests logging at
+        // the same time do not get in the way.
+        thread_local! {
+            static RECORDS: RefCell<Vec<(::log::Level, String, String)>> = RefCell::new(vec![]);
+        }
+
+        struct Capture;
+
+        impl ::log::Log for Capture {
+            fn enabled(&self, _: &::log::Metadata) -> bool {
+                true
+            }
+
+            fn log(&self, record: &::log::Record) {
+                RECORDS.with(|records| {
+                    records.borrow_mut().push((
+                        record.level(),
+                        record.target().to_string(),
+                        record.args().to_string(),
+                    ))
+                });
+            }
+
+            fn flush(&self) {}
+        }
+
+        static CAPTURE: Capture = Capture;
+        ::log::set_logger(&CAPTURE).unwrap();
+        ::log::set_max_level(::log::LevelFilter::Trace);
+
+        let m = MemoryParserHandler::new();
+        {
+            let mut p = Parser::new("ntriples", "http://www.example.com", &m);
+            p.parse_chunk(
+                "<http://www.example.com/s> <http://www.example.com/p> <http://www.example.com/o> .\n\
+                 <http://www.example.com/s> broken .\n",
+            );
+            p.parse_complete();
+        }
+
+        let records = RECORDS.with(|records| records.borrow().clone());
+        assert!(records.iter().any(|&(level, ref target, ref text)| {
+            level == ::log::Level::Error && target == "raptor::parser" && text.contains(":2: ")
+        }));
+    }
+
+    #[test]
+    fn install_handler() {
+        unsafe {
+            let world = raptor_new_world();
+            super::install(world);
+            raptor_free_world(world);
+        }
+    }
+}
@@ -0,0 +1,275 @@
+//! Prefix to IRI bindings, for expanding CURIEs such as `dc:title` and
+//! writing IRIs back in that form.
+use super::push::ParserHandler;
+use super::vocab;
+use super::*;
+
+use std::collections::BTreeMap;
+
+/// A set of prefix bindings. The default namespace has the empty
+/// prefix.
+///
+/// `Namespaces` is also a `ParserHandler` which records the prefixes
+/// declared by a document, so can be filled from a parse by combining
+/// it with another handler.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Namespaces {
+    prefixes: BTreeMap<String, IRI>,
+}
+
+impl Namespaces {
+    pub fn new() -> Namespaces {
+        Namespaces::default()
+    }
+
+    /// The prefixes of the vocabularies in `vocab`, such as `rdfs` and
+    /// `owl`.
+    pub fn well_known() -> Namespaces {
+        let mut namespaces = Namespaces::new();
+        for &(prefix, ns) in vocab::PREFIXES {
+            namespaces.insert(prefix, IRI::new(ns));
+        }
+        namespaces
+    }
+
+    /// Bind `prefix`, returning the IRI it was bound to before.
+    pub fn insert(&mut self, prefix: &str, iri: IRI) -> Option<IRI> {
+        self.prefixes.insert(prefix.to_string(), iri)
+    }
+
+    pub fn remove(&mut self, prefix: &str) -> Option<IRI> {
+        self.prefixes.remove(prefix)
+    }
+
+    pub fn get(&self, prefix: &str) -> Option<&IRI> {
+        self.prefixes.get(prefix)
+    }
+
+    pub fn len(&self) -> usize {
+        self.prefixes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prefixes.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &IRI)> {
+        self.prefixes.iter().map(|(p, iri)| (p.as_str(), iri))
+    }
+
+    /// Record a declaration from a parse. Undeclaring a prefix leaves
+    /// the binding in place, as statements may still be using it.
+    pub fn declare(&mut self, namespace: &Namespace) {
+        if let Some(iri) = namespace.iri() {
+            self.insert(namespace.prefix().unwrap_or(""), iri.clone());
+        }
+    }
+
+    /// Expand a CURIE such as `dc:title`, or `:title` for the default
+    /// namespace.
+    pub fn expand(&self, curie: &str) -> Option<IRI> {
+        let i = curie.find(':')?;
+        self.get(&curie[..i])
+            .map(|namespace| IRI::new(&format!("{}{}", namespace, &curie[i + 1..])))
+    }
+
+    /// The shortest CURIE for `iri`, if any prefix gives one whose local
+    /// part can be written in Turtle without escapes.
+    pub fn compact(&self, iri: &IRI) -> Option<String> {
+        self.prefixes
+            .iter()
+            .filter(|&(_, namespace)| iri.as_str().starts_with(namespace.as_str()))
+            .map(|(prefix, namespace)| (prefix, &iri.as_str()[namespace.as_str().len()..]))
+            .filter(|&(_, local)| is_local_name(local))
+            .map(|(prefix, local)| format!("{}:{}", prefix, local))
+            .min_by_key(|curie| curie.len())
+    }
+
+    /// Display a term in Turtle form, using CURIEs where possible.
+    pub fn term<'a>(&'a self, term: &'a Term) -> Compact<'a, Term> {
+        Compact {
+            namespaces: self,
+            value: term,
+        }
+    }
+
+    /// Display a statement in Turtle form, using CURIEs where possible.
+    pub fn statement<'a>(&'a self, statement: &'a Statement) -> Compact<'a, Statement> {
+        Compact {
+            namespaces: self,
+            value: statement,
+        }
+    }
+
+    fn write_iri(&self, f: &mut fmt::Formatter, iri: &IRI) -> fmt::Result {
+        match self.compact(iri) {
+            Some(curie) => write!(f, "{}", curie),
+            None => write!(f, "<{}>", iri),
+        }
+    }
+
+    fn write_term(&self, f: &mut fmt::Formatter, term: &Term) -> fmt::Result {
+        match *term {
+            Term::URI(ref iri) => self.write_iri(f, iri),
+            Term::Literal(ref literal) => {
+                write!(f, "\"{}\"", escape_literal(literal.value()))?;
+                match (literal.lang(), literal.datatype()) {
+                    (Some(lang), _) => write!(f, "@{}", lang),
+                    (None, Some(datatype)) if datatype.as_str() != XSD_STRING => {
+                        write!(f, "^^")?;
+                        self.write_iri(f, datatype)
+                    }
+                    _ => Ok(()),
+                }
+            }
+            Term::Blank(_) => write!(f, "{}", term),
+        }
+    }
+}
+
+impl ParserHandler for Namespaces {
+    fn handle_statement(&mut self, _: Statement) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn handle_error(&mut self, _: LogMessage) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn handle_namespace(&mut self, namespace: Namespace) -> Result<(), String> {
+        self.declare(&namespace);
+        Ok(())
+    }
+}
+
+// A conservative reading of Turtle's PN_LOCAL: no escapes, and nothing
+// which could be confused with the end of a statement.
+fn is_local_name(local: &str) -> bool {
+    let mut chars = local.chars();
+    match chars.next() {
+        None => return true,
+        Some(c) if c.is_alphanumeric() || c == '_' => {}
+        Some(_) => return false,
+    }
+    !local.ends_with('.')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.')
+}
+
+/// A term or statement displayed with CURIEs; see `Namespaces::term`.
+#[derive(Debug)]
+pub struct Compact<'a, T: 'a> {
+    namespaces: &'a Namespaces,
+    value: &'a T,
+}
+
+impl<'a> fmt::Display for Compact<'a, Term> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.namespaces.write_term(f, self.value)
+    }
+}
+
+/// As for `Statement`, without the line ending.
+impl<'a> fmt::Display for Compact<'a, Statement> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let statement = self.value;
+        self.namespaces.write_term(f, statement.subject())?;
+        write!(f, " ")?;
+        self.namespaces.write_term(f, statement.predicate())?;
+        write!(f, " ")?;
+        self.namespaces.write_term(f, statement.object())?;
+        if let Some(graph) = statement.graph() {
+            write!(f, " ")?;
+            self.namespaces.write_term(f, graph)?;
+        }
+        write!(f, " .")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use push::Parser;
+
+    fn namespaces() -> Namespaces {
+        let mut ns = Namespaces::new();
+        ns.insert("ex", IRI::new("http://www.example.com/"));
+        ns.insert("exa", IRI::new("http://www.example.com/a/"));
+        ns.insert("xsd", IRI::new("http://www.w3.org/2001/XMLSchema#"));
+        ns
+    }
+
+    #[test]
+    fn expand() {
+        let ns = namespaces();
+        assert_eq!(
+            Some(IRI::new("http://www.example.com/title")),
+            ns.expand("ex:title")
+        );
+        assert_eq!(None, ns.expand(":title"));
+        assert_eq!(None, ns.expand("title"));
+    }
+
+    #[test]
+    fn compact() {
+        let ns = namespaces();
+        assert_eq!(
+            Some("exa:b".to_string()),
+            ns.compact(&IRI::new("http://www.example.com/a/b"))
+        );
+        assert_eq!(
+            Some("ex:b".to_string()),
+            ns.compact(&IRI::new("http://www.example.com/b"))
+        );
+        assert_eq!(None, ns.compact(&IRI::new("http://www.example.com/b/c?d")));
+        assert_eq!(None, ns.compact(&IRI::new("http://www.example.org/")));
+    }
+
+    #[test]
+    fn display() {
+        let ns = namespaces();
+        let statement = Statement::new(
+            Term::URI(IRI::new("http://www.example.com/s")),
+            Term::URI(IRI::new("http://www.example.com/p")),
+            Term::Literal(Literal::new(
+                "5",
+                Some(IRI::new("http://www.w3.org/2001/XMLSchema#integer")),
+                None,
+            )),
+            None,
+        );
+        assert_eq!(
+            "ex:s ex:p \"5\"^^xsd:integer .",
+            ns.statement(&statement).to_string()
+        );
+        assert_eq!(
+            "<http://www.example.org/>",
+            ns.term(&Term::URI(IRI::new("http://www.example.org/")))
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn well_known() {
+        let ns = Namespaces::well_known();
+        assert_eq!(Some(IRI::new(vocab::rdfs::LABEL)), ns.expand("rdfs:label"));
+        assert_eq!(
+            Some("owl:Class".to_string()),
+            ns.compact(&IRI::new(vocab::owl::CLASS))
+        );
+    }
+
+    #[test]
+    fn from_parse() {
+        let about = include_str!("./test-files/about.rdf");
+        let ns = Namespaces::new();
+        {
+            let mut p = Parser::new("rdfxml", "http://www.example.com", &ns);
+            p.parse_chunk(about);
+            p.parse_complete();
+        }
+        assert_eq!(
+            Some(&IRI::new("http://purl.org/dc/elements/1.1/")),
+            ns.get("dc")
+        );
+    }
+}
@@ -0,0 +1,438 @@
+//! Parallel parsing of line-based syntaxes.
+//!
+//! Every statement in N-Triples and N-Quads sits on its own line, so a
+//! large document can be cut into chunks at line boundaries and each
+//! chunk parsed independently. Each chunk gets its own `push::Parser`
+//! on a pool of worker threads, and the results are passed to a single
+//! `ParserHandler` on the calling thread.
+//!
+//! libraptor keeps blank node labels from these syntaxes as written, so
+//! a label means the same node in every chunk of a document. Locators
+//! in log messages are relative to the start of the chunk.
+//!
+//! As with `push::Parser`, an error returned by the handler stops the
+//! parse; no more chunks are handed to the workers, and the error is
+//! returned.
+use super::push::MemoryParserHandler;
+use super::push::Parser;
+use super::push::ParserHandler;
+use super::*;
+
+use std::collections::BTreeMap;
+use std::sync::mpsc::channel;
+use std::sync::mpsc::sync_channel;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::SyncSender;
+use std::sync::mpsc::TrySendError;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+
+const DEFAULT_THREADS: usize = 4;
+const DEFAULT_CHUNK_SIZE: usize = 1024 * 1024;
+
+type Event = Result<Statement, LogMessage>;
+
+// The events of a parsed job, with its index and document.
+type Parsed = (usize, usize, Vec<Event>);
+
+#[derive(Clone, Debug)]
+pub struct ParallelParser {
+    syntax: Syntax,
+    baseuri: String,
+    threads: usize,
+    chunk_size: usize,
+    ordered: bool,
+}
+
+// A chunk of a document, numbered in the order of the input.
+struct Job {
+    index: usize,
+    document: usize,
+    content: String,
+}
+
+impl ParallelParser {
+    /// A parser for `syntax`, which must be N-Triples or N-Quads.
+    pub fn new(syntax: Syntax, baseuri: &str) -> Result<ParallelParser, String> {
+        match syntax {
+            Syntax::NTriples | Syntax::NQuads => Ok(ParallelParser {
+                syntax,
+                baseuri: baseuri.to_string(),
+                threads: DEFAULT_THREADS,
+                chunk_size: DEFAULT_CHUNK_SIZE,
+                ordered: true,
+            }),
+            _ => Err(format!("{} is not a line based syntax", syntax)),
+        }
+    }
+
+    pub fn threads(mut self, threads: usize) -> ParallelParser {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// The approximate size of each chunk in bytes; chunks are extended
+    /// to the end of the line they would otherwise split.
+    pub fn chunk_size(mut self, chunk_size: usize) -> ParallelParser {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Whether events reach the handler in document order, or as soon
+    /// as their chunk has been parsed.
+    pub fn ordered(mut self, ordered: bool) -> ParallelParser {
+        self.ordered = ordered;
+        self
+    }
+
+    pub fn parse(&self, content: &str, handler: &mut ParserHandler) -> Result<(), String> {
+        self.run(&[content], false, handler)
+    }
+
+    /// Parse several documents as one.
+    ///
+    /// Blank node labels are scoped to their document, so `_:b1` in two
+    /// documents will be two different nodes.
+    pub fn parse_documents(
+        &self,
+        documents: &[&str],
+        handler: &mut ParserHandler,
+    ) -> Result<(), String> {
+        self.run(documents, true, handler)
+    }
+
+    fn run(
+        &self,
+        documents: &[&str],
+        scoped: bool,
+        handler: &mut ParserHandler,
+    ) -> Result<(), String> {
+        let chunks: Vec<(usize, &str)> = documents
+            .iter()
+            .enumerate()
+            .flat_map(|(document, content)| {
+                split_lines(content, self.chunk_size)
+                    .into_iter()
+                    .map(move |chunk| (document, chunk))
+            })
+            .collect();
+
+        // Only a few chunks are copied for the workers at a time, rather
+        // than the whole input up front.
+        let (job_sender, job_receiver) = sync_channel::<Job>(self.threads * 2);
+        let (result_sender, result_receiver) = channel::<Parsed>();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+        let workers: Vec<_> = (0..self.threads.min(chunks.len()))
+            .map(|_| {
+                let job_receiver = job_receiver.clone();
+                let result_sender = result_sender.clone();
+                let syntax = self.syntax;
+                let baseuri = self.baseuri.clone();
+                thread::spawn(move || loop {
+                    let job = match job_receiver.lock().unwrap().recv() {
+                        Ok(job) => job,
+                        Err(_) => return,
+                    };
+
+                    let m = MemoryParserHandler::new();
+                    {
+                        let mut p = Parser::new(syntax.name(), &baseuri, &m);
+                        p.parse_chunk(&job.content);
+                        p.parse_complete();
+                    }
+                    let events = m.0.into_iter().collect();
+                    if result_sender.send((job.index, job.document, events)).is_err() {
+                        return;
+                    }
+                })
+            })
+            .collect();
+        drop(result_sender);
+
+        let mut delivery = Delivery {
+            scoped,
+            ordered: self.ordered,
+            pending: BTreeMap::new(),
+            next: 0,
+        };
+
+        let result = self
+            .feed(chunks, job_sender, &result_receiver, &mut delivery, handler)
+            .and_then(|()| {
+                for parsed in result_receiver.iter() {
+                    delivery.deliver(parsed, handler)?;
+                }
+                Ok(())
+            });
+
+        // After an error, the workers finish the jobs already queued and
+        // stop when they cannot send the results.
+        drop(result_receiver);
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        result
+    }
+
+    // Queue every chunk for the workers. While the queue is full, results
+    // are delivered to make way. If the order is kept, no chunk is queued
+    // more than a queue's length ahead of the next to be delivered, so
+    // that a slow chunk cannot leave all those after it held back.
+    fn feed(
+        &self,
+        chunks: Vec<(usize, &str)>,
+        job_sender: SyncSender<Job>,
+        results: &Receiver<Parsed>,
+        delivery: &mut Delivery,
+        handler: &mut ParserHandler,
+    ) -> Result<(), String> {
+        let lookahead = self.threads * 2;
+        for (index, (document, chunk)) in chunks.into_iter().enumerate() {
+            while delivery.ordered && index >= delivery.next + lookahead {
+                delivery.deliver(results.recv().unwrap(), handler)?;
+            }
+
+            let mut job = Job {
+                index,
+                document,
+                content: chunk.to_string(),
+            };
+            loop {
+                match job_sender.try_send(job) {
+                    Ok(()) => break,
+                    Err(TrySendError::Full(unsent)) => {
+                        job = unsent;
+                        delivery.deliver(results.recv().unwrap(), handler)?;
+                    }
+                    Err(TrySendError::Disconnected(_)) => panic!("parser threads have stopped"),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Hands parsed jobs to the handler, holding back those which arrive
+// ahead of their turn if the order is kept.
+struct Delivery {
+    scoped: bool,
+    ordered: bool,
+    pending: BTreeMap<usize, (Vec<Event>, Option<usize>)>,
+    next: usize,
+}
+
+impl Delivery {
+    fn deliver(
+        &mut self,
+        (index, document, events): Parsed,
+        handler: &mut ParserHandler,
+    ) -> Result<(), String> {
+        let scope = if self.scoped { Some(document) } else { None };
+        if !self.ordered {
+            return deliver(events, scope, handler);
+        }
+
+        self.pending.insert(index, (events, scope));
+        while let Some((events, scope)) = self.pending.remove(&self.next) {
+            deliver(events, scope, handler)?;
+            self.next += 1;
+        }
+        Ok(())
+    }
+}
+
+// Split content into chunks of roughly `chunk_size` bytes, each ending
+// at the end of a line. A newline byte is never part of a multi-byte
+// character, so the splits are always on character boundaries.
+fn split_lines(content: &str, chunk_size: usize) -> Vec<&str> {
+    let mut chunks = vec![];
+    let mut rest = content;
+    while !rest.is_empty() {
+        if rest.len() <= chunk_size {
+            chunks.push(rest);
+            break;
+        }
+        let end = match rest.as_bytes()[chunk_size..]
+            .iter()
+            .position(|&b| b == b'\n')
+        {
+            Some(i) => chunk_size + i + 1,
+            None => rest.len(),
+        };
+        chunks.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    chunks
+}
+
+fn scope_term(term: Term, document: usize) -> Term {
+    match term {
+        Term::Blank(label) => Term::Blank(format!("d{}_{}", document, label)),
+        other => other,
+    }
+}
+
+fn deliver(
+    events: Vec<Event>,
+    scope: Option<usize>,
+    handler: &mut ParserHandler,
+) -> Result<(), String> {
+    for event in events {
+        match event {
+            Ok(statement) => {
+                let statement = match scope {
+                    Some(document) => Statement {
+                        subject: scope_term(statement.subject, document),
+                        predicate: statement.predicate,
+                        object: scope_term(statement.object, document),
+                        graph: statement.graph.map(|g| scope_term(g, document)),
+                    },
+                    None => statement,
+                };
+                handler.handle_statement(statement)?;
+            }
+            Err(message) => {
+                handler.handle_error(message)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graph::Graph;
+    use handler::FnHandler;
+
+    fn ntriples(n: usize) -> String {
+        (0..n)
+            .map(|i| {
+                format!(
+                    "<http://www.example.com/s{0}> <http://www.example.com/p> _:b{1} .\n",
+                    i,
+                    i % 3
+                )
+            })
+            .collect()
+    }
+
+    fn sequential(content: &str) -> Vec<Event> {
+        let m = MemoryParserHandler::new();
+        {
+            let mut p = Parser::new("ntriples", "http://www.example.com", &m);
+            p.parse_chunk(content);
+            p.parse_complete();
+        }
+        m.0.into_iter().collect()
+    }
+
+    #[test]
+    fn split() {
+        assert_eq!(vec!["a\n", "b\n", "c"], split_lines("a\nb\nc", 1));
+        assert_eq!(vec!["a\nb\n", "c"], split_lines("a\nb\nc", 3));
+        assert_eq!(vec!["a\nb\nc"], split_lines("a\nb\nc", 10));
+        assert!(split_lines("", 10).is_empty());
+    }
+
+    #[test]
+    fn not_line_based() {
+        assert!(ParallelParser::new(Syntax::RdfXml, "http://www.example.com").is_err());
+    }
+
+    #[test]
+    fn ordered() {
+        let content = ntriples(100);
+        let mut m = MemoryParserHandler::new();
+        ParallelParser::new(Syntax::NTriples, "http://www.example.com")
+            .unwrap()
+            .threads(3)
+            .chunk_size(200)
+            .parse(&content, &mut m)
+            .unwrap();
+
+        let expected: Vec<Statement> = sequential(&content)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .collect();
+        let parsed: Vec<Statement> = m.0.into_iter().filter_map(|e| e.ok()).collect();
+        assert_eq!(expected, parsed);
+    }
+
+    #[test]
+    fn unordered() {
+        let content = ntriples(100);
+        let mut m = MemoryParserHandler::new();
+        ParallelParser::new(Syntax::NTriples, "http://www.example.com")
+            .unwrap()
+            .threads(3)
+            .chunk_size(200)
+            .ordered(false)
+            .parse(&content, &mut m)
+            .unwrap();
+
+        let expected: Graph = sequential(&content)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .collect();
+        let parsed: Graph = m.0.into_iter().filter_map(|e| e.ok()).collect();
+        assert!(expected.is_isomorphic(&parsed));
+    }
+
+    // Many more chunks than the queue holds.
+    #[test]
+    fn many_chunks() {
+        let content = ntriples(1000);
+        let mut m = MemoryParserHandler::new();
+        ParallelParser::new(Syntax::NTriples, "http://www.example.com")
+            .unwrap()
+            .threads(2)
+            .chunk_size(1)
+            .parse(&content, &mut m)
+            .unwrap();
+
+        let parsed: Vec<Statement> = m.0.into_iter().filter_map(|e| e.ok()).collect();
+        assert_eq!(1000, parsed.len());
+    }
+
+    #[test]
+    fn document_scope() {
+        let document = "_:b1 <http://www.example.com/p> <http://www.example.com/o> .\n";
+        let mut m = MemoryParserHandler::new();
+        ParallelParser::new(Syntax::NTriples, "http://www.example.com")
+            .unwrap()
+            .parse_documents(&[document, document], &mut m)
+            .unwrap();
+
+        let graph: Graph = m.0.into_iter().filter_map(|e| e.ok()).collect();
+        assert_eq!(2, graph.len());
+    }
+
+    #[test]
+    fn handler_error() {
+        let content = ntriples(1000);
+        for &ordered in &[true, false] {
+            let mut count = 0;
+            let result = {
+                let mut h = FnHandler::new().on_statement(|_| {
+                    count += 1;
+                    if count == 5 {
+                        Err("enough".to_string())
+                    } else {
+                        Ok(())
+                    }
+                });
+                ParallelParser::new(Syntax::NTriples, "http://www.example.com")
+                    .unwrap()
+                    .threads(2)
+                    .chunk_size(1)
+                    .ordered(ordered)
+                    .parse(&content, &mut h)
+            };
+            assert_eq!(Err("enough".to_string()), result);
+            assert_eq!(5, count);
+        }
+    }
+}
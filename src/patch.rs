@@ -0,0 +1,589 @@
+//! RDF Patch, a line-based format for the changes made to a dataset.
+//!
+//! A patch is a sequence of operations: headers (`H`), transaction
+//! markers (`TX`, `TC`, `TA`), prefix changes (`PA`, `PD`) and the
+//! addition (`A`) or deletion (`D`) of statements.
+//!
+//! ```text
+//! H id <uuid:0686c69d-8f89-4496-acb5-744f0157a8db> .
+//! TX .
+//! PA ex: <http://www.example.com/> .
+//! D <http://www.example.com/s> ex:p "old" .
+//! A <http://www.example.com/s> ex:p "new" .
+//! TC .
+//! ```
+use super::graph::Diff;
+use super::graph::Graph;
+use super::*;
+
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Operation {
+    Header(String, Term),
+    TxBegin,
+    TxCommit,
+    TxAbort,
+    PrefixAdd(String, IRI),
+    PrefixDelete(String),
+    Add(Statement),
+    Delete(Statement),
+}
+
+/// Operations display as a single line of a patch, without the
+/// newline.
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Operation::Header(ref key, ref value) => write!(f, "H {} {} .", key, value),
+            Operation::TxBegin => write!(f, "TX ."),
+            Operation::TxCommit => write!(f, "TC ."),
+            Operation::TxAbort => write!(f, "TA ."),
+            Operation::PrefixAdd(ref prefix, ref iri) => write!(f, "PA {}: <{}> .", prefix, iri),
+            Operation::PrefixDelete(ref prefix) => write!(f, "PD {}: .", prefix),
+            Operation::Add(ref statement) => write!(f, "A {}", statement),
+            Operation::Delete(ref statement) => write!(f, "D {}", statement),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Patch {
+    operations: Vec<Operation>,
+}
+
+impl Patch {
+    pub fn new() -> Patch {
+        Patch::default()
+    }
+
+    pub fn push(&mut self, operation: Operation) {
+        self.operations.push(operation)
+    }
+
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    /// A single transaction deleting the statements removed by `diff`
+    /// and adding those it added.
+    ///
+    /// Blank nodes keep their labels, so the two graphs diffed should
+    /// not share any labels unless they mean the same node; otherwise
+    /// applying the patch can join unrelated nodes.
+    pub fn from_diff(diff: &Diff) -> Patch {
+        let mut patch = Patch::new();
+        patch.push(Operation::TxBegin);
+        for statement in diff.removed().iter() {
+            patch.push(Operation::Delete(statement.clone()));
+        }
+        for statement in diff.added().iter() {
+            patch.push(Operation::Add(statement.clone()));
+        }
+        patch.push(Operation::TxCommit);
+        patch
+    }
+
+    /// Parse a patch. Prefixed names in statements are expanded using
+    /// the `PA` rows before them.
+    pub fn parse(content: &str) -> Result<Patch, String> {
+        let mut patch = Patch::new();
+        let mut prefixes = BTreeMap::new();
+
+        for (n, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let operation =
+                parse_line(line, &mut prefixes).map_err(|e| format!("line {}: {}", n + 1, e))?;
+            patch.push(operation);
+        }
+        Ok(patch)
+    }
+
+    /// Apply the patch to `graph`.
+    ///
+    /// Changes in an aborted transaction are dropped. The patch is
+    /// checked before anything is changed, so a badly nested
+    /// transaction leaves the graph as it was.
+    pub fn apply(&self, graph: &mut Graph) -> Result<(), String> {
+        let mut changes = vec![];
+        let mut pending: Option<Vec<&Operation>> = None;
+
+        for operation in &self.operations {
+            match *operation {
+                Operation::TxBegin => {
+                    if pending.is_some() {
+                        return Err("Transactions cannot be nested".to_string());
+                    }
+                    pending = Some(vec![]);
+                }
+                Operation::TxCommit => match pending.take() {
+                    Some(committed) => changes.extend(committed),
+                    None => return Err("TC outside a transaction".to_string()),
+                },
+                Operation::TxAbort => {
+                    if pending.take().is_none() {
+                        return Err("TA outside a transaction".to_string());
+                    }
+                }
+                Operation::Add(_) | Operation::Delete(_) => match pending {
+                    Some(ref mut pending) => pending.push(operation),
+                    None => changes.push(operation),
+                },
+                _ => {}
+            }
+        }
+
+        if pending.is_some() {
+            return Err("Transaction is not finished".to_string());
+        }
+
+        for change in changes {
+            match *change {
+                Operation::Add(ref statement) => {
+                    graph.insert(statement.clone());
+                }
+                Operation::Delete(ref statement) => {
+                    graph.remove(statement);
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Patch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for operation in &self.operations {
+            writeln!(f, "{}", operation)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    IRI(String),
+    Blank(String),
+    Literal(String, Option<String>, Option<Box<Token>>),
+    // A keyword, or a prefixed name.
+    Name(String),
+    Dot,
+}
+
+fn parse_line(line: &str, prefixes: &mut BTreeMap<String, IRI>) -> Result<Operation, String> {
+    let mut tokens = tokenize(line)?;
+    if tokens.last() == Some(&Token::Dot) {
+        tokens.pop();
+    }
+
+    let mut tokens = tokens.into_iter();
+    let code = match tokens.next() {
+        Some(Token::Name(code)) => code,
+        _ => return Err(format!("Expected an operation: {}", line)),
+    };
+    let args: Vec<Token> = tokens.collect();
+
+    let operation = match (code.as_str(), args.len()) {
+        ("H", 2) => {
+            let mut args = args.into_iter();
+            let key = match args.next() {
+                Some(Token::Name(key)) => key,
+                _ => return Err(format!("Expected a header name: {}", line)),
+            };
+            Operation::Header(key, to_term(args.next().unwrap(), prefixes)?)
+        }
+        ("TX", 0) => Operation::TxBegin,
+        ("TC", 0) => Operation::TxCommit,
+        ("TA", 0) => Operation::TxAbort,
+        ("PA", 2) => {
+            let mut args = args.into_iter();
+            let prefix = to_prefix(args.next().unwrap())?;
+            let iri = match args.next().unwrap() {
+                Token::IRI(iri) => IRI::new(&iri),
+                _ => return Err(format!("Expected a namespace IRI: {}", line)),
+            };
+            prefixes.insert(prefix.clone(), iri.clone());
+            Operation::PrefixAdd(prefix, iri)
+        }
+        ("PD", 1) => {
+            let prefix = to_prefix(args.into_iter().next().unwrap())?;
+            prefixes.remove(&prefix);
+            Operation::PrefixDelete(prefix)
+        }
+        ("A", 3) | ("A", 4) | ("D", 3) | ("D", 4) => {
+            let mut terms = vec![];
+            for arg in args {
+                terms.push(to_term(arg, prefixes)?);
+            }
+            let mut terms = terms.into_iter();
+            let statement = Statement::new(
+                terms.next().unwrap(),
+                terms.next().unwrap(),
+                terms.next().unwrap(),
+                terms.next(),
+            );
+            if code == "A" {
+                Operation::Add(statement)
+            } else {
+                Operation::Delete(statement)
+            }
+        }
+        _ => return Err(format!("Cannot understand: {}", line)),
+    };
+    Ok(operation)
+}
+
+// Prefixes may be written `ex:`, `ex` or `"ex"`.
+fn to_prefix(token: Token) -> Result<String, String> {
+    match token {
+        Token::Name(name) => Ok(name.trim_end_matches(':').to_string()),
+        Token::Literal(name, None, None) => Ok(name),
+        token => Err(format!("Expected a prefix, not {:?}", token)),
+    }
+}
+
+fn expand(name: &str, prefixes: &BTreeMap<String, IRI>) -> Result<IRI, String> {
+    let i = name
+        .find(':')
+        .ok_or_else(|| format!("Expected a prefixed name: {}", name))?;
+    match prefixes.get(&name[..i]) {
+        Some(namespace) => Ok(IRI::new(&format!("{}{}", namespace, &name[i + 1..]))),
+        None => Err(format!("Undeclared prefix: {}", &name[..i])),
+    }
+}
+
+fn to_term(token: Token, prefixes: &BTreeMap<String, IRI>) -> Result<Term, String> {
+    match token {
+        Token::IRI(iri) => Ok(Term::URI(IRI::new(&iri))),
+        Token::Blank(label) => Ok(Term::Blank(label)),
+        Token::Name(name) => Ok(Term::URI(expand(&name, prefixes)?)),
+        Token::Literal(value, lang, datatype) => {
+            let datatype = match datatype.map(|d| *d) {
+                Some(Token::IRI(iri)) => Some(IRI::new(&iri)),
+                Some(Token::Name(name)) => Some(expand(&name, prefixes)?),
+                Some(token) => return Err(format!("Expected a datatype, not {:?}", token)),
+                None => None,
+            };
+            Ok(Term::Literal(Literal::new(
+                &value,
+                datatype,
+                lang.as_ref().map(|l| l.as_str()),
+            )))
+        }
+        Token::Dot => Err("Unexpected .".to_string()),
+    }
+}
+
+fn tokenize(line: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = line.chars().peekable();
+
+    loop {
+        while chars.peek().map_or(false, |c| c.is_whitespace()) {
+            chars.next();
+        }
+
+        let token = match chars.peek().cloned() {
+            None => return Ok(tokens),
+            Some('#') => return Ok(tokens),
+            Some('<') => {
+                chars.next();
+                Token::IRI(read_until(&mut chars, '>')?)
+            }
+            Some('"') => {
+                chars.next();
+                let value = read_until(&mut chars, '"')?;
+                let mut lang = None;
+                let mut datatype = None;
+                match chars.peek().cloned() {
+                    Some('@') => {
+                        chars.next();
+                        lang = Some(read_word(&mut chars));
+                    }
+                    Some('^') => {
+                        chars.next();
+                        if chars.next() != Some('^') {
+                            return Err("Expected ^^".to_string());
+                        }
+                        datatype = match chars.peek().cloned() {
+                            Some('<') => {
+                                chars.next();
+                                Some(Box::new(Token::IRI(read_until(&mut chars, '>')?)))
+                            }
+                            _ => Some(Box::new(Token::Name(read_word(&mut chars)))),
+                        };
+                    }
+                    _ => {}
+                }
+                Token::Literal(value, lang, datatype)
+            }
+            Some('_') => {
+                chars.next();
+                if chars.next() != Some(':') {
+                    return Err("Expected _:".to_string());
+                }
+                Token::Blank(read_word(&mut chars))
+            }
+            Some('.') => {
+                chars.next();
+                Token::Dot
+            }
+            Some(_) => Token::Name(read_word(&mut chars)),
+        };
+
+        // A word directly followed by the final dot, as `ex:o.`, or
+        // `"o"@en.` and `"o"^^ex:t.` where the word ends a literal. The
+        // dot may be followed by a comment.
+        let mut token = token;
+        let mut rest = chars.clone();
+        while rest.peek().map_or(false, |c| c.is_whitespace()) {
+            rest.next();
+        }
+        let at_end = match rest.peek().cloned() {
+            None | Some('#') => true,
+            Some(_) => false,
+        };
+        let trailing_dot = at_end
+            && match token {
+                Token::Name(ref mut word)
+                | Token::Blank(ref mut word)
+                | Token::Literal(_, Some(ref mut word), None) => strip_dot(word),
+                Token::Literal(_, None, Some(ref mut datatype)) => match **datatype {
+                    Token::Name(ref mut word) => strip_dot(word),
+                    _ => false,
+                },
+                _ => false,
+            };
+        tokens.push(token);
+        if trailing_dot {
+            tokens.push(Token::Dot);
+        }
+    }
+}
+
+// Take the dot off the end of `word`, unless it is all there is.
+fn strip_dot(word: &mut String) -> bool {
+    if word.len() > 1 && word.ends_with('.') {
+        word.pop();
+        true
+    } else {
+        false
+    }
+}
+
+fn read_word(chars: &mut Peekable<Chars>) -> String {
+    let mut word = String::new();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            break;
+        }
+        word.push(c);
+        chars.next();
+    }
+    word
+}
+
+// Read up to `end`, which is consumed, unescaping as N-Triples.
+fn read_until(chars: &mut Peekable<Chars>, end: char) -> Result<String, String> {
+    let mut value = String::new();
+    loop {
+        match chars.next() {
+            None => return Err(format!("Expected {}", end)),
+            Some(c) if c == end => return Ok(value),
+            Some('\\') => {
+                let c = match chars.next() {
+                    Some('t') => '\t',
+                    Some('b') => '\u{8}',
+                    Some('n') => '\n',
+                    Some('r') => '\r',
+                    Some('f') => '\u{c}',
+                    Some('"') => '"',
+                    Some('\'') => '\'',
+                    Some('>') => '>',
+                    Some('\\') => '\\',
+                    Some('u') => read_hex(chars, 4)?,
+                    Some('U') => read_hex(chars, 8)?,
+                    c => return Err(format!("Bad escape: {:?}", c)),
+                };
+                value.push(c);
+            }
+            Some(c) => value.push(c),
+        }
+    }
+}
+
+fn read_hex(chars: &mut Peekable<Chars>, digits: usize) -> Result<char, String> {
+    let hex: String = chars.by_ref().take(digits).collect();
+    u32::from_str_radix(&hex, 16)
+        .ok()
+        .filter(|_| hex.len() == digits)
+        .and_then(std::char::from_u32)
+        .ok_or_else(|| format!("Bad escape: \\u{}", hex))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn iri(s: &str) -> Term {
+        Term::URI(IRI::new(s))
+    }
+
+    #[test]
+    fn parse() {
+        let patch = Patch::parse(
+            "H id <uuid:0686c69d> .\n\
+             TX .\n\
+             PA ex: <http://www.example.com/> .\n\
+             D ex:s ex:p \"old\"@en .\n\
+             A _:b0 ex:p \"line\\none\"^^ex:text <http://www.example.com/g> .\n\
+             # a comment\n\
+             PD ex: .\n\
+             TC .\n",
+        )
+        .unwrap();
+
+        assert_eq!(7, patch.operations().len());
+        assert_eq!(
+            Operation::Header("id".to_string(), iri("uuid:0686c69d")),
+            patch.operations()[0]
+        );
+        assert_eq!(
+            Operation::Delete(Statement::new(
+                iri("http://www.example.com/s"),
+                iri("http://www.example.com/p"),
+                Term::Literal(Literal::new("old", None, Some("en"))),
+                None,
+            )),
+            patch.operations()[3]
+        );
+        assert_eq!(
+            Operation::Add(Statement::new(
+                Term::Blank("b0".to_string()),
+                iri("http://www.example.com/p"),
+                Term::Literal(Literal::new(
+                    "line\none",
+                    Some(IRI::new("http://www.example.com/text")),
+                    None
+                )),
+                Some(iri("http://www.example.com/g")),
+            )),
+            patch.operations()[4]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert!(Patch::parse("A ex:s ex:p ex:o .").is_err());
+        assert!(Patch::parse("X .").is_err());
+        assert!(Patch::parse("A <s> <p> \"o .").is_err());
+    }
+
+    #[test]
+    fn final_dot() {
+        let patch = Patch::parse(
+            "PA ex: <http://www.example.com/> .\n\
+             A <s> <p> \"o\"@en.\n\
+             A <s> <p> \"1\"^^ex:int.\n",
+        )
+        .unwrap();
+        assert_eq!(
+            Operation::Add(Statement::new(
+                iri("s"),
+                iri("p"),
+                Term::Literal(Literal::new("o", None, Some("en"))),
+                None,
+            )),
+            patch.operations()[1]
+        );
+        assert_eq!(
+            Operation::Add(Statement::new(
+                iri("s"),
+                iri("p"),
+                Term::Literal(Literal::new(
+                    "1",
+                    Some(IRI::new("http://www.example.com/int")),
+                    None
+                )),
+                None,
+            )),
+            patch.operations()[2]
+        );
+    }
+
+    #[test]
+    fn final_dot_and_comment() {
+        let patch = Patch::parse(
+            "PA ex: <http://www.example.com/> .\n\
+             A <s> <p> ex:o. # note\n",
+        )
+        .unwrap();
+        assert_eq!(
+            Operation::Add(Statement::new(
+                iri("s"),
+                iri("p"),
+                iri("http://www.example.com/o"),
+                None,
+            )),
+            patch.operations()[1]
+        );
+    }
+
+    #[test]
+    fn prefix_targets() {
+        assert!(Patch::parse("PA ex: <http://www.example.com/> .").is_ok());
+        assert!(Patch::parse("PA ex: \"http://www.example.com/\" .").is_err());
+        assert!(Patch::parse("PA ex: _:b1 .").is_err());
+        assert!(Patch::parse("PA ex: ex:a .").is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let a: Graph = vec![
+            Statement::new(iri("s"), iri("p"), iri("o"), None),
+            Statement::new(
+                iri("s"),
+                iri("p"),
+                Term::Literal(Literal::new("tab\there \"quoted\"", None, None)),
+                None,
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let b: Graph = vec![
+            Statement::new(iri("s"), iri("p"), iri("o"), None),
+            Statement::new(iri("s"), iri("p"), Term::Blank("x".to_string()), None),
+        ]
+        .into_iter()
+        .collect();
+
+        let patch = Patch::from_diff(&a.diff(&b));
+        let reparsed = Patch::parse(&patch.to_string()).unwrap();
+        assert_eq!(patch, reparsed);
+
+        let mut graph = a.clone();
+        reparsed.apply(&mut graph).unwrap();
+        assert_eq!(b, graph);
+    }
+
+    #[test]
+    fn transactions() {
+        let mut graph = Graph::new();
+        Patch::parse("TX .\nA <s> <p> <o> .\nTA .\nA <s> <p> <o2> .")
+            .unwrap()
+            .apply(&mut graph)
+            .unwrap();
+        assert_eq!(1, graph.len());
+        assert!(graph.contains(&Statement::new(iri("s"), iri("p"), iri("o2"), None)));
+
+        let patch = Patch::parse("TX .\nA <s> <p> <o3> .").unwrap();
+        assert!(patch.apply(&mut graph).is_err());
+        assert_eq!(1, graph.len());
+    }
+}
@@ -1,12 +1,53 @@
+use super::iostream::ReadStream;
 use super::*;
 
 use std::collections::VecDeque;
 use std::ffi::CStr;
 use std::ffi::CString;
+use std::io::Read;
+use std::path::Path;
 
 pub trait ParserHandler: Debug {
     fn handle_statement(&mut self, Statement) -> Result<(), String>;
     fn handle_error(&mut self, LogMessage) -> Result<(), String>;
+
+    // Most handlers have no use for namespaces, so they are ignored
+    // unless asked for.
+    fn handle_namespace(&mut self, _namespace: Namespace) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Called instead of `handle_statement` by a parser capturing
+    /// locators; see `Parser::locators`.
+    fn handle_located_statement(
+        &mut self,
+        statement: Statement,
+        _locator: Locator,
+    ) -> Result<(), String> {
+        self.handle_statement(statement)
+    }
+}
+
+impl<'a, H: ParserHandler + ?Sized> ParserHandler for &'a mut H {
+    fn handle_statement(&mut self, statement: Statement) -> Result<(), String> {
+        (**self).handle_statement(statement)
+    }
+
+    fn handle_error(&mut self, error: LogMessage) -> Result<(), String> {
+        (**self).handle_error(error)
+    }
+
+    fn handle_namespace(&mut self, namespace: Namespace) -> Result<(), String> {
+        (**self).handle_namespace(namespace)
+    }
+
+    fn handle_located_statement(
+        &mut self,
+        statement: Statement,
+        locator: Locator,
+    ) -> Result<(), String> {
+        (**self).handle_located_statement(statement, locator)
+    }
 }
 
 #[derive(Copy, Clone, Debug, Default)]
@@ -48,10 +89,27 @@ impl ParserHandler for MemoryParserHandler {
 pub struct Parser {
     raw: *mut raptor_parser,
     raw_world: *mut raptor_world,
+    raw_base: *mut raptor_uri,
+    // The user data for statements, which libraptor holds a pointer to.
+    context: Box<HandlerContext>,
+}
+
+// The callbacks need the parser as well as the handler, to ask for the
+// locator and to stop the parse when the handler returns an error.
+struct HandlerContext {
+    handler: *mut c_void,
+    parser: *mut raptor_parser,
+    locators: bool,
 }
 
 impl<'w> Parser {
+    /// A parser for the syntax libraptor calls `kind`.
+    ///
+    /// If libraptor has no such parser, as for syntaxes it can only
+    /// serialize, `handler` is sent a fatal error, and the parser
+    /// does nothing.
     pub fn new(kind: &str, baseuri: &str, handler: &ParserHandler) -> Parser {
+        let name = kind;
         let kind = CString::new(kind).unwrap();
         let baseuri = CString::new(baseuri).unwrap();
 
@@ -63,26 +121,73 @@ impl<'w> Parser {
             let double_boxed_handler: Box<Box<&ParserHandler>> = Box::new(Box::new(handler));
             let handler_ptr = Box::into_raw(double_boxed_handler) as *mut _;
 
-            let parser = Parser {
-                raw: raptor_new_parser(world, kind.as_ptr()),
+            let raw = raptor_new_parser(world, kind.as_ptr());
+            let mut parser = Parser {
+                raw,
                 raw_world: world,
+                raw_base: baseuri,
+                context: Box::new(HandlerContext {
+                    handler: handler_ptr,
+                    parser: raw,
+                    locators: false,
+                }),
             };
 
-            raptor_world_set_log_handler(parser.raw_world, handler_ptr, Some(log_handler));
+            if raw.is_null() {
+                let message = LogMessage {
+                    text: format!("No parser for {}", name),
+                    level: LogLevel::Fatal,
+                    domain: Some("parser".to_string()),
+                    locator: None,
+                };
+                #[cfg(any(feature = "log", feature = "tracing"))]
+                logging::emit(&message);
+                let ph: &mut Box<&mut ParserHandler> = mem::transmute(handler_ptr);
+                ph.handle_error(message).ok();
+                return parser;
+            }
 
-            raptor_parser_set_statement_handler(parser.raw, handler_ptr, Some(statement_handler));
+            let context_ptr = &mut *parser.context as *mut HandlerContext as *mut c_void;
+            raptor_world_set_log_handler(parser.raw_world, context_ptr, Some(log_handler));
+            raptor_parser_set_statement_handler(parser.raw, context_ptr, Some(statement_handler));
+            raptor_parser_set_namespace_handler(parser.raw, context_ptr, Some(namespace_handler));
 
             raptor_parser_parse_start(parser.raw, baseuri);
             parser
         }
     }
 
+    /// Whether to pass each statement to `handle_located_statement`,
+    /// with the parser's position when it was found. For most syntaxes
+    /// this is the end of the statement.
+    pub fn locators(mut self, capture: bool) -> Parser {
+        self.context.locators = capture;
+        self
+    }
+
     fn parse_cstr(&mut self, content: &CStr, size: usize) {
+        if self.raw.is_null() {
+            return;
+        }
         unsafe {
             raptor_parser_parse_chunk(self.raw, content.as_ptr() as *const u8, size, 0);
         }
     }
 
+    /// Parse some bytes of the document.
+    ///
+    /// Unlike `parse_chunk`, the content need not end on a character
+    /// boundary, so this is suitable for feeding a document through
+    /// in fixed size blocks.
+    pub fn parse_bytes(&mut self, content: &[u8]) {
+        if self.raw.is_null() {
+            return;
+        }
+        unsafe {
+            raptor_parser_parse_chunk(self.raw, content.as_ptr(), content.len(), 0);
+        }
+    }
+
     pub fn parse_chunk(&mut self, content: &str) {
         let len = content.len();
         let c = CString::new(content).unwrap();
@@ -90,26 +195,68 @@ impl<'w> Parser {
     }
 
     pub fn parse_complete(&mut self) {
+        if self.raw.is_null() {
+            return;
+        }
         unsafe {
             raptor_parser_parse_chunk(self.raw, std::ptr::null(), 0, 1);
         }
     }
+
+    /// Parse a whole document, letting libraptor pull it from `reader`.
+    ///
+    /// This starts the parse again, so should not be mixed with
+    /// `parse_chunk` or `parse_bytes`. Syntax errors go to the handler;
+    /// the result is for errors reading.
+    pub fn parse_read<R: Read>(&mut self, reader: R) -> io::Result<()> {
+        if self.raw.is_null() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "No such parser"));
+        }
+        unsafe {
+            let mut stream = ReadStream::new(self.raw_world, reader);
+            raptor_parser_parse_iostream(self.raw, stream.as_raw(), self.raw_base);
+            match stream.take_error() {
+                Some(error) => Err(error),
+                None => Ok(()),
+            }
+        }
+    }
+
+    /// Parse the file at `path` as with `parse_read`, decompressing it
+    /// as it is read if it is compressed; see `compression`.
+    pub fn parse_file<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        self.parse_read(compression::open(path.as_ref())?)
+    }
 }
 
 impl<'w> Drop for Parser {
     fn drop(&mut self) {
         unsafe {
-            raptor_free_parser(self.raw);
+            if !self.raw.is_null() {
+                raptor_free_parser(self.raw);
+            }
+            raptor_free_uri(self.raw_base);
             raptor_free_world(self.raw_world);
         }
     }
 }
 
+// A handler returning an error stops the parse; the handler is
+// expected to keep hold of the error itself if it wants it.
+unsafe fn abort_on_error(context: &HandlerContext, result: Result<(), String>) {
+    if result.is_err() {
+        raptor_parser_parse_abort(context.parser);
+    }
+}
+
 extern "C" fn log_handler(user_data: *mut c_void, message: *mut raptor_log_message) {
     unsafe {
-        let ph: &mut Box<&mut ParserHandler> = mem::transmute(user_data);
+        let context = &*(user_data as *mut HandlerContext);
+        let ph: &mut Box<&mut ParserHandler> = mem::transmute(context.handler);
         let rust_log_message = raptor_log_message_to_rust_log_message(message);
-        ph.handle_error(rust_log_message).ok();
+        #[cfg(any(feature = "log", feature = "tracing"))]
+        logging::emit(&rust_log_message);
+        abort_on_error(context, ph.handle_error(rust_log_message));
     }
 }
 
@@ -117,19 +264,32 @@ extern "C" fn statement_handler(user_data: *mut c_void, statement: *mut raptor_s
     unsafe {
         let rust_statement = raptor_statement_to_rust_statement(statement);
 
-        let ph: &mut Box<&mut ParserHandler> = mem::transmute(user_data);
-        ph.handle_statement(rust_statement).ok();
+        let context = &*(user_data as *mut HandlerContext);
+        let ph: &mut Box<&mut ParserHandler> = mem::transmute(context.handler);
+        let result = if context.locators {
+            let locator = raptor_locator_to_rust_locator(raptor_parser_get_locator(context.parser));
+            ph.handle_located_statement(rust_statement, locator)
+        } else {
+            ph.handle_statement(rust_statement)
+        };
+        abort_on_error(context, result);
+    }
+}
+
+extern "C" fn namespace_handler(user_data: *mut c_void, namespace: *mut raptor_namespace) {
+    unsafe {
+        let rust_namespace = raptor_namespace_to_rust_namespace(namespace);
+
+        let context = &*(user_data as *mut HandlerContext);
+        let ph: &mut Box<&mut ParserHandler> = mem::transmute(context.handler);
+        abort_on_error(context, ph.handle_namespace(rust_namespace));
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::ffi::OsStr;
-    use std::fs;
-    use std::fs::read_dir;
-    use std::io::Error;
-    use std::path::Path;
+
     #[test]
     fn raw_new_free_world() {
         unsafe {
@@ -172,6 +332,93 @@ mod tests {
         p.parse_complete();
     }
 
+    #[derive(Debug, Default)]
+    struct LocatorHandler(Vec<(Statement, Locator)>);
+
+    impl ParserHandler for LocatorHandler {
+        fn handle_statement(&mut self, _: Statement) -> Result<(), String> {
+            panic!("Statement without locator");
+        }
+
+        fn handle_error(&mut self, _: LogMessage) -> Result<(), String> {
+            Ok(())
+        }
+
+        fn handle_located_statement(
+            &mut self,
+            statement: Statement,
+            locator: Locator,
+        ) -> Result<(), String> {
+            self.0.push((statement, locator));
+            Ok(())
+        }
+    }
+
+    // dot can only be serialized.
+    #[test]
+    fn serializer_only_syntax() {
+        let m = MemoryParserHandler::new();
+        {
+            let mut p = Parser::new("dot", "http://www.example.com", &m);
+            p.parse_chunk("digraph {}");
+            p.parse_complete();
+        }
+
+        assert_eq!(1, m.0.len());
+        match m.0[0] {
+            Err(ref message) => assert_eq!(LogLevel::Fatal, message.level()),
+            Ok(_) => panic!("Statement from no parser"),
+        }
+    }
+
+    #[test]
+    fn test_locators() {
+        let nt = "<http://www.example.com/s> <http://www.example.com/p> \"1\" .\n\
+                  <http://www.example.com/s> <http://www.example.com/p> \"2\" .\n";
+        let h = LocatorHandler::default();
+        let mut p = Parser::new("ntriples", "http://www.example.com", &h).locators(true);
+        p.parse_chunk(nt);
+        p.parse_complete();
+
+        assert_eq!(2, h.0.len());
+        assert_eq!(Some(1), h.0[0].1.line());
+        assert_eq!(Some(2), h.0[1].1.line());
+    }
+
+    #[test]
+    fn test_parse_file() {
+        let h = MemoryParserHandler::new();
+        {
+            let mut p = Parser::new("rdfxml", "http://www.example.com", &h);
+            p.parse_file("src/test-files/about.rdf").unwrap();
+        }
+        assert!(h.0.iter().all(|event| event.is_ok()));
+        assert!(!h.0.is_empty());
+    }
+
+    #[test]
+    fn test_parse_read() {
+        let about = include_str!("./test-files/about_two.rdf");
+        let m = MemoryParserHandler::new();
+        let mut p = Parser::new("rdfxml", "http://www.example.com", &m);
+        p.parse_read(about.as_bytes()).unwrap();
+
+        assert_eq!(2, m.0.len());
+    }
+
+    #[test]
+    fn test_parse_bytes() {
+        let about = include_str!("./test-files/about_two.rdf");
+        let m = MemoryParserHandler::new();
+        let mut p = Parser::new("rdfxml", "http://www.example.com", &m);
+        for chunk in about.as_bytes().chunks(5) {
+            p.parse_bytes(chunk);
+        }
+        p.parse_complete();
+
+        assert_eq!(2, m.0.len());
+    }
+
     #[test]
     fn test_two_parse() {
         let about = include_str!("./test-files/about_two.rdf");
@@ -201,55 +448,4 @@ mod tests {
             Ok(())
         }
     }
-
-    #[test]
-    fn w3c_test_suite() -> Result<(), Error> {
-        // TODO Rewrite this out of copyright
-
-        let rdf_ext = Some(OsStr::new("rdf"));
-        let suite = Path::new("./").join("rdf-tests").join("rdf-xml");
-        if !suite.exists() || !suite.is_dir() {
-            panic!("rdf-tests/rdf-xml not found");
-        }
-
-        let test_files = read_dir(&suite)
-            .unwrap()
-            .map(|subfile| subfile.ok().unwrap().path())
-            .filter(|p| p.is_dir())
-            .flat_map(|subdir| read_dir(subdir).unwrap())
-            .map(|entry| entry.ok().unwrap().path())
-            .filter(|path| path.extension() == rdf_ext);
-
-        let mut count = 0;
-        for path in test_files {
-            let st = fs::read_to_string(path.clone())?;
-
-            let m = MemoryParserHandler::new();
-            let mut p = Parser::new("rdfxml", "http://www.example.com", &m);
-            p.parse_chunk(&st);
-            p.parse_complete();
-
-            count = count + 1;
-
-            let path = path.file_name().unwrap().to_str().unwrap();
-
-            if path.starts_with("error") || path.starts_with("warn") {
-                assert!(
-                    m.0.iter().any(|event| event.is_err()),
-                    format!("{} should NOT parse without error", path)
-                );
-            } else {
-                assert!(
-                    m.0.iter().all(|event| event.is_ok()),
-                    format!("{} should parse without error", path)
-                );
-            }
-        }
-        assert_ne!(
-            count, 0,
-            "No test found in W3C test-suite, something must be wrong"
-        );
-
-        Ok(())
-    }
 }
@@ -0,0 +1,595 @@
+use super::iostream::WriteStream;
+use super::namespaces::Namespaces;
+use super::push::ParserHandler;
+use super::*;
+
+use std::ffi::CString;
+use std::io::Write;
+
+/// Serializes statements into one of libraptor's output syntaxes.
+///
+/// Output is either collected by libraptor and handed back as a string
+/// by `finish`, or written as it is produced to a `Write` given to
+/// `to_writer`.
+#[derive(Debug)]
+pub struct Serializer<'w> {
+    raw: *mut raptor_serializer,
+    raw_world: *mut raptor_world,
+    raw_base: *mut raptor_uri,
+    // libraptor writes the location and length of the output here when
+    // the serialization ends, so these must not move.
+    output: Box<(*mut c_void, usize)>,
+    stream: Option<WriteStream<'w>>,
+}
+
+impl<'w> Serializer<'w> {
+    /// A serializer collecting its output as a string. This fails if
+    /// libraptor has no serializer called `kind`, as for syntaxes it
+    /// can only parse.
+    pub fn new(kind: &str, baseuri: Option<&str>) -> Result<Serializer<'w>, String> {
+        Ok(Serializer::create(kind, baseuri)?.start_to_string())
+    }
+
+    /// As `new`, with options set before serialization starts.
+    pub fn with_options(
+        kind: &str,
+        baseuri: Option<&str>,
+        options: &SerializerOptions,
+    ) -> Result<Serializer<'w>, String> {
+        let serializer = Serializer::create(kind, baseuri)?;
+        options.apply(serializer.raw)?;
+        Ok(serializer.start_to_string())
+    }
+
+    /// A serializer writing to `writer`; finish it with `close`.
+    ///
+    /// libraptor writes a byte at a time in places, so an unbuffered
+    /// writer should be wrapped in a `BufWriter`.
+    pub fn to_writer<W: Write + 'w>(
+        kind: &str,
+        baseuri: Option<&str>,
+        writer: W,
+    ) -> Result<Serializer<'w>, String> {
+        Ok(Serializer::create(kind, baseuri)?.start_to_writer(writer))
+    }
+
+    /// As `to_writer`, with options set before serialization starts.
+    pub fn to_writer_with_options<W: Write + 'w>(
+        kind: &str,
+        baseuri: Option<&str>,
+        options: &SerializerOptions,
+        writer: W,
+    ) -> Result<Serializer<'w>, String> {
+        let serializer = Serializer::create(kind, baseuri)?;
+        options.apply(serializer.raw)?;
+        Ok(serializer.start_to_writer(writer))
+    }
+
+    /// The serializers libraptor provides.
+    pub fn descriptions() -> Vec<SyntaxDescription> {
+        let mut descriptions = vec![];
+        unsafe {
+            let world = raptor_new_world();
+            #[cfg(any(feature = "log", feature = "tracing"))]
+            logging::install(world);
+            raptor_world_open(world);
+            loop {
+                let description =
+                    raptor_world_get_serializer_description(world, descriptions.len() as u32);
+                if description.is_null() {
+                    break;
+                }
+                descriptions.push(raptor_syntax_description_to_rust(description));
+            }
+            raptor_free_world(world);
+        }
+        descriptions
+    }
+
+    fn create(kind: &str, baseuri: Option<&str>) -> Result<Serializer<'w>, String> {
+        let name = kind;
+        let kind = CString::new(kind).map_err(|e| e.to_string())?;
+
+        unsafe {
+            let world = raptor_new_world();
+            #[cfg(any(feature = "log", feature = "tracing"))]
+            logging::install(world);
+
+            let raw_base = match baseuri {
+                Some(baseuri) => {
+                    let baseuri = CString::new(baseuri).unwrap();
+                    raptor_new_uri(world, baseuri.as_ptr() as *const u8)
+                }
+                None => std::ptr::null_mut(),
+            };
+
+            let raw = raptor_new_serializer(world, kind.as_ptr());
+            if raw.is_null() {
+                if !raw_base.is_null() {
+                    raptor_free_uri(raw_base);
+                }
+                raptor_free_world(world);
+                return Err(format!("No serializer for {}", name));
+            }
+
+            Ok(Serializer {
+                raw,
+                raw_world: world,
+                raw_base,
+                output: Box::new((std::ptr::null_mut(), 0)),
+                stream: None,
+            })
+        }
+    }
+
+    fn start_to_string(mut self) -> Serializer<'w> {
+        unsafe {
+            let output = &mut *self.output;
+            raptor_serializer_start_to_string(
+                self.raw,
+                self.raw_base,
+                &mut output.0,
+                &mut output.1,
+            );
+        }
+        self
+    }
+
+    fn start_to_writer<W: Write + 'w>(mut self, writer: W) -> Serializer<'w> {
+        unsafe {
+            let stream = WriteStream::new(self.raw_world, writer);
+            raptor_serializer_start_to_iostream(self.raw, self.raw_base, stream.as_raw());
+            self.stream = Some(stream);
+        }
+        self
+    }
+
+    /// Declare a prefix, or the default namespace if there is no
+    /// prefix.
+    ///
+    /// Syntaxes which write their namespaces in a header ignore any
+    /// declared after the first statement.
+    pub fn set_namespace(&mut self, prefix: Option<&str>, iri: &IRI) -> Result<(), String> {
+        let c_prefix = match prefix {
+            Some(prefix) => Some(CString::new(prefix).map_err(|e| e.to_string())?),
+            None => None,
+        };
+
+        unsafe {
+            let uri = rust_iri_to_raptor_uri(self.raw_world, iri);
+            let rtn = raptor_serializer_set_namespace(
+                self.raw,
+                uri,
+                c_prefix
+                    .as_ref()
+                    .map_or(std::ptr::null(), |p| p.as_ptr() as *const u8),
+            );
+            raptor_free_uri(uri);
+
+            if rtn == 0 {
+                Ok(())
+            } else {
+                Err(format!(
+                    "Failed to declare {}: {}",
+                    prefix.unwrap_or(""),
+                    iri
+                ))
+            }
+        }
+    }
+
+    /// Declare every prefix in `namespaces` which the serializer will
+    /// take, skipping any it will not, such as those it already
+    /// declares or all of them for N-Triples.
+    pub fn set_namespaces(&mut self, namespaces: &Namespaces) {
+        for (prefix, iri) in namespaces.iter() {
+            let prefix = if prefix.is_empty() { None } else { Some(prefix) };
+            self.set_namespace(prefix, iri).ok();
+        }
+    }
+
+    pub fn serialize_statement(&mut self, statement: &Statement) -> Result<(), String> {
+        unsafe {
+            let raw_statement = rust_statement_to_raptor_statement(self.raw_world, statement)?;
+            let rtn = raptor_serializer_serialize_statement(self.raw, raw_statement);
+            raptor_free_statement(raw_statement);
+
+            if rtn == 0 {
+                Ok(())
+            } else {
+                Err(format!("Failed to serialize {}", statement))
+            }
+        }
+    }
+
+    /// End the serialization, returning the output. This is empty for
+    /// a serializer made with `to_writer`.
+    pub fn finish(mut self) -> String {
+        unsafe {
+            raptor_serializer_serialize_end(self.raw);
+            if let Some(ref mut stream) = self.stream {
+                stream.flush().ok();
+            }
+
+            let (ptr, len) = *self.output;
+            if ptr.is_null() {
+                return String::new();
+            }
+
+            let output =
+                String::from_utf8_lossy(std::slice::from_raw_parts(ptr as *const u8, len))
+                    .into_owned();
+            raptor_free_memory(ptr);
+            self.output.0 = std::ptr::null_mut();
+            output
+        }
+    }
+
+    /// End the serialization, flushing the writer, and returning the
+    /// first error seen while writing.
+    pub fn close(mut self) -> io::Result<()> {
+        unsafe {
+            raptor_serializer_serialize_end(self.raw);
+        }
+        match self.stream {
+            Some(ref mut stream) => stream.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'w> Drop for Serializer<'w> {
+    fn drop(&mut self) {
+        unsafe {
+            raptor_free_serializer(self.raw);
+            // Freeing the serializer may have produced output, even if
+            // it was never finished.
+            if !self.output.0.is_null() {
+                raptor_free_memory(self.output.0);
+            }
+            // The stream belongs to the world, so must go first.
+            self.stream.take();
+            if !self.raw_base.is_null() {
+                raptor_free_uri(self.raw_base);
+            }
+            raptor_free_world(self.raw_world);
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum OptionValue {
+    Int(i32),
+    Str(String),
+}
+
+/// Options for a `Serializer`, for `Serializer::with_options`.
+///
+/// Options which a syntax does not use are ignored by it; those for
+/// `dot` and the feed syntaxes are only meaningful there.
+///
+/// ```ignore
+/// let options = SerializerOptions::new().relative_uris(true).write_base_uri(false);
+/// let serializer = Serializer::with_options("turtle", Some(base), &options)?;
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SerializerOptions {
+    options: Vec<(raptor_option, OptionValue)>,
+}
+
+impl SerializerOptions {
+    pub fn new() -> SerializerOptions {
+        SerializerOptions::default()
+    }
+
+    fn int(mut self, option: raptor_option, value: i32) -> SerializerOptions {
+        self.options.push((option, OptionValue::Int(value)));
+        self
+    }
+
+    fn string(mut self, option: raptor_option, value: &str) -> SerializerOptions {
+        self.options.push((option, OptionValue::Str(value.to_string())));
+        self
+    }
+
+    /// Write IRIs relative to the base where possible.
+    pub fn relative_uris(self, relative: bool) -> SerializerOptions {
+        self.int(raptor_option_RAPTOR_OPTION_RELATIVE_URIS, relative as i32)
+    }
+
+    /// Declare the base IRI in the output, as Turtle's `@base`.
+    pub fn write_base_uri(self, write: bool) -> SerializerOptions {
+        self.int(raptor_option_RAPTOR_OPTION_WRITE_BASE_URI, write as i32)
+    }
+
+    /// Start XML output with an `<?xml ...?>` declaration.
+    pub fn xml_declaration(self, declaration: bool) -> SerializerOptions {
+        self.int(
+            raptor_option_RAPTOR_OPTION_WRITER_XML_DECLARATION,
+            declaration as i32,
+        )
+    }
+
+    pub fn auto_indent(self, indent: bool) -> SerializerOptions {
+        self.int(raptor_option_RAPTOR_OPTION_WRITER_AUTO_INDENT, indent as i32)
+    }
+
+    pub fn indent_width(self, width: i32) -> SerializerOptions {
+        self.int(raptor_option_RAPTOR_OPTION_WRITER_INDENT_WIDTH, width)
+    }
+
+    /// The border colour of IRI nodes in `dot` output.
+    pub fn resource_border(self, colour: &str) -> SerializerOptions {
+        self.string(raptor_option_RAPTOR_OPTION_RESOURCE_BORDER, colour)
+    }
+
+    /// The border colour of literal nodes in `dot` output.
+    pub fn literal_border(self, colour: &str) -> SerializerOptions {
+        self.string(raptor_option_RAPTOR_OPTION_LITERAL_BORDER, colour)
+    }
+
+    /// The border colour of blank nodes in `dot` output.
+    pub fn bnode_border(self, colour: &str) -> SerializerOptions {
+        self.string(raptor_option_RAPTOR_OPTION_BNODE_BORDER, colour)
+    }
+
+    /// The fill colour of IRI nodes in `dot` output.
+    pub fn resource_fill(self, colour: &str) -> SerializerOptions {
+        self.string(raptor_option_RAPTOR_OPTION_RESOURCE_FILL, colour)
+    }
+
+    /// The fill colour of literal nodes in `dot` output.
+    pub fn literal_fill(self, colour: &str) -> SerializerOptions {
+        self.string(raptor_option_RAPTOR_OPTION_LITERAL_FILL, colour)
+    }
+
+    /// The fill colour of blank nodes in `dot` output.
+    pub fn bnode_fill(self, colour: &str) -> SerializerOptions {
+        self.string(raptor_option_RAPTOR_OPTION_BNODE_FILL, colour)
+    }
+
+    /// How `atom` and `rss-1.0` write statements which do not fit the
+    /// feed: `none`, `rdf-xml` or `atom-triples`.
+    pub fn rss_triples(self, kind: &str) -> SerializerOptions {
+        self.string(raptor_option_RAPTOR_OPTION_RSS_TRIPLES, kind)
+    }
+
+    /// Write a single Atom entry with this IRI, rather than a feed.
+    pub fn atom_entry_uri(self, iri: &str) -> SerializerOptions {
+        self.string(raptor_option_RAPTOR_OPTION_ATOM_ENTRY_URI, iri)
+    }
+
+    /// Use prefixed element names in `atom` and `rss-1.0` output.
+    pub fn prefix_elements(self, prefix: bool) -> SerializerOptions {
+        self.int(raptor_option_RAPTOR_OPTION_PREFIX_ELEMENTS, prefix as i32)
+    }
+
+    fn apply(&self, serializer: *mut raptor_serializer) -> Result<(), String> {
+        for &(option, ref value) in &self.options {
+            let rtn = unsafe {
+                match *value {
+                    OptionValue::Int(i) => {
+                        raptor_serializer_set_option(serializer, option, std::ptr::null(), i)
+                    }
+                    OptionValue::Str(ref s) => {
+                        let c = CString::new(s.as_str()).map_err(|e| e.to_string())?;
+                        raptor_serializer_set_option(serializer, option, c.as_ptr(), 0)
+                    }
+                }
+            };
+            if rtn != 0 {
+                return Err(format!("Failed to set serializer option {}", option));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A `ParserHandler` which passes statements and namespace
+/// declarations straight on to a `Serializer`.
+///
+/// Log messages are dropped; combine this with another handler to see
+/// them.
+#[derive(Debug)]
+pub struct SerializerHandler<'a, 'w: 'a> {
+    serializer: &'a mut Serializer<'w>,
+}
+
+impl<'a, 'w> SerializerHandler<'a, 'w> {
+    pub fn new(serializer: &'a mut Serializer<'w>) -> SerializerHandler<'a, 'w> {
+        SerializerHandler { serializer }
+    }
+}
+
+impl<'a, 'w> ParserHandler for SerializerHandler<'a, 'w> {
+    fn handle_statement(&mut self, statement: Statement) -> Result<(), String> {
+        self.serializer.serialize_statement(&statement)
+    }
+
+    fn handle_error(&mut self, _: LogMessage) -> Result<(), String> {
+        Ok(())
+    }
+
+    // A serializer may not take a prefix it already declares, one after
+    // its header, or any at all, as with N-Triples. None of these is a
+    // reason to stop the parse, so failures are ignored.
+    fn handle_namespace(&mut self, namespace: Namespace) -> Result<(), String> {
+        if let Some(ref iri) = namespace.iri {
+            self.serializer.set_namespace(namespace.prefix(), iri).ok();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use push::MemoryParserHandler;
+    use push::Parser;
+
+    fn statement() -> Statement {
+        Statement::new(
+            Term::URI(IRI::new("http://www.example.com/s")),
+            Term::URI(IRI::new("http://www.example.com/p")),
+            Term::Literal(Literal::new("o", None, Some("en"))),
+            None,
+        )
+    }
+
+    #[test]
+    fn new_serializer() {
+        let _s = Serializer::new("ntriples", None).unwrap();
+    }
+
+    #[test]
+    fn parser_only_syntax() {
+        assert!(Serializer::new("rdfa", None).is_err());
+        assert!(Serializer::to_writer("guess", None, Vec::<u8>::new()).is_err());
+    }
+
+    #[test]
+    fn serialize_ntriples() {
+        let mut s = Serializer::new("ntriples", None).unwrap();
+        s.serialize_statement(&statement()).unwrap();
+
+        assert_eq!(
+            "<http://www.example.com/s> <http://www.example.com/p> \"o\"@en .\n",
+            s.finish()
+        );
+    }
+
+    #[test]
+    fn serialize_to_writer() {
+        let mut output = vec![];
+        {
+            let mut s = Serializer::to_writer("ntriples", None, &mut output).unwrap();
+            s.serialize_statement(&statement()).unwrap();
+            s.close().unwrap();
+        }
+
+        assert_eq!(
+            "<http://www.example.com/s> <http://www.example.com/p> \"o\"@en .\n",
+            String::from_utf8(output).unwrap()
+        );
+    }
+
+    #[test]
+    fn serialize_with_namespace() {
+        let mut s = Serializer::new("turtle", Some("http://www.example.com/")).unwrap();
+        s.set_namespace(Some("ex"), &IRI::new("http://www.example.com/"))
+            .unwrap();
+        s.serialize_statement(&statement()).unwrap();
+
+        let turtle = s.finish();
+        assert!(turtle.contains("@prefix ex: <http://www.example.com/> ."));
+        assert!(turtle.contains("ex:s"));
+    }
+
+    #[test]
+    fn set_namespaces_skips_failures() {
+        let mut namespaces = Namespaces::new();
+        namespaces.insert("rdf", IRI::new("http://www.w3.org/1999/02/22-rdf-syntax-ns#"));
+        namespaces.insert("ex", IRI::new("http://www.example.com/"));
+
+        let mut s = Serializer::new("turtle", None).unwrap();
+        s.set_namespaces(&namespaces);
+        s.serialize_statement(&statement()).unwrap();
+        assert!(s.finish().contains("@prefix ex: <http://www.example.com/> ."));
+
+        let mut s = Serializer::new("ntriples", None).unwrap();
+        s.set_namespaces(&namespaces);
+        s.serialize_statement(&statement()).unwrap();
+        assert!(s.finish().contains("<http://www.example.com/s>"));
+    }
+
+    #[test]
+    fn serializer_handler() {
+        let about = include_str!("./test-files/about.rdf");
+        let mut s = Serializer::new("turtle", Some("http://www.example.com/")).unwrap();
+        {
+            let h = SerializerHandler::new(&mut s);
+            let mut p = Parser::new("rdfxml", "http://www.example.com", &h);
+            p.parse_chunk(about);
+            p.parse_complete();
+        }
+
+        assert!(s.finish().contains("@prefix dc: <http://purl.org/dc/elements/1.1/> ."));
+    }
+
+    // about_two.rdf declares rdf:, which Turtle predeclares and
+    // N-Triples has no use for.
+    #[test]
+    fn serializer_handler_prefixes() {
+        let about = include_str!("./test-files/about_two.rdf");
+        for syntax in &["turtle", "ntriples"] {
+            let mut s = Serializer::new(syntax, Some("http://www.example.com/")).unwrap();
+            {
+                let h = SerializerHandler::new(&mut s);
+                let mut p = Parser::new("rdfxml", "http://www.example.com", &h);
+                p.parse_chunk(about);
+                p.parse_complete();
+            }
+            let output = s.finish();
+
+            let m = MemoryParserHandler::new();
+            {
+                let mut p = Parser::new(syntax, "http://www.example.com/", &m);
+                p.parse_chunk(&output);
+                p.parse_complete();
+            }
+            assert_eq!(2, m.0.iter().filter(|e| e.is_ok()).count(), "{}", syntax);
+        }
+    }
+
+    #[test]
+    fn serialize_with_options() {
+        let options = SerializerOptions::new()
+            .relative_uris(true)
+            .write_base_uri(false);
+        let mut s =
+            Serializer::with_options("turtle", Some("http://www.example.com/"), &options).unwrap();
+        s.serialize_statement(&statement()).unwrap();
+
+        let turtle = s.finish();
+        assert!(!turtle.contains("@base"));
+        assert!(turtle.contains("<s>"));
+    }
+
+    #[test]
+    fn descriptions() {
+        let descriptions = Serializer::descriptions();
+        let turtle = descriptions
+            .iter()
+            .find(|d| d.syntax() == Some(Syntax::Turtle))
+            .unwrap();
+        assert!(turtle
+            .mime_types()
+            .iter()
+            .any(|&(ref mime_type, _)| mime_type == "text/turtle"));
+    }
+
+    #[test]
+    fn serialize_unrepresentable() {
+        let mut s = Serializer::new("ntriples", None).unwrap();
+        let statement = Statement::new(
+            Term::URI(IRI::new("http://www.example.com/s")),
+            Term::URI(IRI::new("http://www.example.com/p")),
+            Term::Literal(Literal::new(
+                "o",
+                Some(IRI::new("http://www.example.com/t")),
+                Some("en"),
+            )),
+            None,
+        );
+        assert!(s.serialize_statement(&statement).is_err());
+        assert_eq!("", s.finish());
+    }
+
+    #[test]
+    fn serialize_turtle() {
+        let mut s = Serializer::new("turtle", Some("http://www.example.com/")).unwrap();
+        s.serialize_statement(&statement()).unwrap();
+
+        assert!(s.finish().contains("\"o\"@en"));
+    }
+}
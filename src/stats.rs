@@ -0,0 +1,180 @@
+use super::push::ParserHandler;
+use super::*;
+
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+
+/// A `ParserHandler` which keeps statistics about a parse rather than
+/// the statements themselves.
+///
+/// Distinct terms are remembered so that they can be counted, but
+/// nothing else is, so this is considerably cheaper than collecting a
+/// parse into a `MemoryParserHandler`.
+#[derive(Debug, Default)]
+pub struct StatsHandler {
+    triples: usize,
+    subjects: HashSet<Term>,
+    predicates: HashSet<Term>,
+    objects: HashSet<Term>,
+    blank_nodes: HashSet<String>,
+    graphs: HashSet<Term>,
+    datatypes: BTreeMap<IRI, usize>,
+    languages: BTreeMap<String, usize>,
+    messages: BTreeMap<LogLevel, usize>,
+}
+
+impl StatsHandler {
+    pub fn new() -> StatsHandler {
+        StatsHandler::default()
+    }
+
+    pub fn triples(&self) -> usize {
+        self.triples
+    }
+
+    pub fn distinct_subjects(&self) -> usize {
+        self.subjects.len()
+    }
+
+    pub fn distinct_predicates(&self) -> usize {
+        self.predicates.len()
+    }
+
+    pub fn distinct_objects(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn blank_nodes(&self) -> usize {
+        self.blank_nodes.len()
+    }
+
+    pub fn graphs(&self) -> usize {
+        self.graphs.len()
+    }
+
+    /// The number of literals of each datatype.
+    pub fn datatypes(&self) -> &BTreeMap<IRI, usize> {
+        &self.datatypes
+    }
+
+    /// The number of literals with each language tag.
+    pub fn languages(&self) -> &BTreeMap<String, usize> {
+        &self.languages
+    }
+
+    /// The number of log messages at each level.
+    pub fn messages(&self) -> &BTreeMap<LogLevel, usize> {
+        &self.messages
+    }
+
+    /// The number of error or fatal log messages.
+    pub fn errors(&self) -> usize {
+        self.messages
+            .iter()
+            .filter(|&(level, _)| *level >= LogLevel::Error)
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    fn record_term(&mut self, term: &Term) {
+        match term {
+            Term::Blank(label) => {
+                self.blank_nodes.insert(label.clone());
+            }
+            Term::Literal(literal) => {
+                if let Some(ref datatype) = literal.datatype {
+                    *self.datatypes.entry(datatype.clone()).or_insert(0) += 1;
+                }
+                if let Some(ref lang) = literal.lang {
+                    *self.languages.entry(lang.clone()).or_insert(0) += 1;
+                }
+            }
+            Term::URI(_) => {}
+        }
+    }
+}
+
+impl ParserHandler for StatsHandler {
+    fn handle_statement(&mut self, statement: Statement) -> Result<(), String> {
+        self.triples += 1;
+        self.record_term(&statement.subject);
+        self.record_term(&statement.object);
+        if let Some(ref graph) = statement.graph {
+            self.record_term(graph);
+            self.graphs.insert(graph.clone());
+        }
+
+        self.subjects.insert(statement.subject);
+        self.predicates.insert(statement.predicate);
+        self.objects.insert(statement.object);
+        Ok(())
+    }
+
+    fn handle_error(&mut self, message: LogMessage) -> Result<(), String> {
+        *self.messages.entry(message.level).or_insert(0) += 1;
+        Ok(())
+    }
+}
+
+impl fmt::Display for StatsHandler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Triples: {}", self.triples)?;
+        writeln!(f, "Distinct subjects: {}", self.distinct_subjects())?;
+        writeln!(f, "Distinct predicates: {}", self.distinct_predicates())?;
+        writeln!(f, "Distinct objects: {}", self.distinct_objects())?;
+        writeln!(f, "Blank nodes: {}", self.blank_nodes())?;
+        writeln!(f, "Named graphs: {}", self.graphs())?;
+
+        writeln!(f, "Datatypes:")?;
+        for (datatype, count) in &self.datatypes {
+            writeln!(f, "  {}: {}", datatype, count)?;
+        }
+        writeln!(f, "Languages:")?;
+        for (lang, count) in &self.languages {
+            writeln!(f, "  {}: {}", lang, count)?;
+        }
+        writeln!(f, "Messages:")?;
+        for (level, count) in &self.messages {
+            writeln!(f, "  {}: {}", level, count)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use push::Parser;
+
+    #[test]
+    fn test_stats() {
+        let about = include_str!("./test-files/about_with_lang.rdf");
+        let s = StatsHandler::new();
+        {
+            let mut p = Parser::new("rdfxml", "http://www.example.com", &s);
+            p.parse_chunk(about);
+            p.parse_complete();
+        }
+
+        assert_eq!(1, s.triples());
+        assert_eq!(1, s.distinct_subjects());
+        assert_eq!(1, s.distinct_predicates());
+        assert_eq!(1, s.distinct_objects());
+        assert_eq!(0, s.blank_nodes());
+        assert_eq!(0, s.errors());
+        assert_eq!(1, s.languages().len());
+        assert_eq!(1, s.languages()["en"]);
+    }
+
+    #[test]
+    fn test_empty_stats() {
+        let s = StatsHandler::new();
+        {
+            let mut p = Parser::new("rdfxml", "http://www.example.com", &s);
+            p.parse_complete();
+        }
+
+        assert_eq!(0, s.triples());
+        assert_eq!(1, s.messages().values().sum::<usize>());
+    }
+}
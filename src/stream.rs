@@ -0,0 +1,195 @@
+//! Parsing from an asynchronous reader.
+//!
+//! Enabled with the `async` feature. The reader is polled for blocks
+//! of bytes which are pushed through a `push::Parser`, and the
+//! statements produced are yielded as a `Stream`, so a parse never
+//! blocks the executor waiting for input.
+use super::push::MemoryParserHandler;
+use super::push::Parser;
+use super::*;
+
+use futures::io::AsyncRead;
+use futures::task::Context;
+use futures::task::Poll;
+use futures::Stream;
+
+use std::pin::Pin;
+
+const DEFAULT_CHUNK_SIZE: usize = 8192;
+
+/// A stream of the statements parsed from an `AsyncRead`.
+///
+/// Errors from libraptor appear in the stream as `Error::Parse`, so
+/// `try_collect` and the like stop at the first; warnings and lesser
+/// messages are left out. An `Error::Io` ends the stream.
+pub struct StatementStream<R> {
+    reader: R,
+    // Dropped before the handler it refers to.
+    parser: Option<Parser>,
+    handler: Box<MemoryParserHandler>,
+    buffer: Vec<u8>,
+}
+
+// The parser, its world and the handler they write to belong to the
+// stream alone, and libraptor keeps nothing per thread, so the stream
+// can move to another thread with its reader, as `tokio::spawn` needs.
+unsafe impl<R: Send> Send for StatementStream<R> {}
+
+/// Parse `reader` as `syntax`, resolving relative IRIs against
+/// `baseuri`.
+///
+/// The base cannot be left out, as there is no file to take it from;
+/// a document without relative IRIs can be given any base.
+pub fn parse_stream<R: AsyncRead + Unpin>(
+    reader: R,
+    syntax: Syntax,
+    baseuri: &str,
+) -> StatementStream<R> {
+    let handler = Box::new(MemoryParserHandler::new());
+    let parser = Parser::new(syntax.name(), baseuri, &*handler);
+
+    StatementStream {
+        reader,
+        parser: Some(parser),
+        handler,
+        buffer: vec![0; DEFAULT_CHUNK_SIZE],
+    }
+}
+
+impl<R> StatementStream<R> {
+    /// Read at most `size` bytes at a time. A size of zero is taken as
+    /// one, as a zero length read would look like the end of input.
+    pub fn chunk_size(mut self, size: usize) -> StatementStream<R> {
+        self.buffer = vec![0; size.max(1)];
+        self
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for StatementStream<R> {
+    type Item = Result<Statement, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        loop {
+            // Hand out whatever the last chunk produced before reading
+            // any more.
+            if let Some(event) = this.handler.0.pop_front() {
+                match event {
+                    Err(ref message) if message.level() < LogLevel::Error => continue,
+                    event => return Poll::Ready(Some(event.map_err(Error::Parse))),
+                }
+            }
+
+            let read = match this.parser {
+                Some(_) => Pin::new(&mut this.reader).poll_read(cx, &mut this.buffer),
+                None => return Poll::Ready(None),
+            };
+
+            match read {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(e)) => {
+                    this.parser = None;
+                    return Poll::Ready(Some(Err(Error::Io(e))));
+                }
+                Poll::Ready(Ok(0)) => {
+                    if let Some(mut parser) = this.parser.take() {
+                        parser.parse_complete();
+                    }
+                }
+                Poll::Ready(Ok(n)) => {
+                    if let Some(ref mut parser) = this.parser {
+                        parser.parse_bytes(&this.buffer[..n]);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::io::Cursor;
+    use futures::StreamExt;
+    use futures::TryStreamExt;
+
+    #[test]
+    fn stream_statements() {
+        let about = include_str!("./test-files/about_two.rdf");
+        let stream = parse_stream(
+            Cursor::new(about.as_bytes()),
+            Syntax::RdfXml,
+            "http://www.example.com",
+        )
+        .chunk_size(7);
+
+        let events: Vec<_> = block_on(stream.collect());
+        assert_eq!(2, events.len());
+        assert!(events.iter().all(|event| event.is_ok()));
+    }
+
+    // Unqualified rdf attributes are deprecated, which libraptor warns
+    // about.
+    #[test]
+    fn stream_warnings() {
+        let rdf = "<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\" \
+                   xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\
+                   <rdf:Description about=\"http://www.example.com/\">\
+                   <dc:title>Example</dc:title>\
+                   </rdf:Description></rdf:RDF>";
+        let stream = parse_stream(
+            Cursor::new(rdf.as_bytes()),
+            Syntax::RdfXml,
+            "http://www.example.com",
+        );
+
+        let statements: Result<Vec<_>, _> = block_on(stream.try_collect());
+        assert_eq!(1, statements.unwrap().len());
+    }
+
+    // Parsing on another thread, as an async runtime might.
+    #[test]
+    fn stream_on_thread() {
+        let about = include_str!("./test-files/about_two.rdf");
+        let stream = parse_stream(
+            Cursor::new(about.as_bytes()),
+            Syntax::RdfXml,
+            "http://www.example.com",
+        );
+
+        let events = std::thread::spawn(move || block_on(stream.collect::<Vec<_>>()))
+            .join()
+            .unwrap();
+        assert_eq!(2, events.len());
+    }
+
+    #[test]
+    fn zero_chunk_size() {
+        let about = include_str!("./test-files/about_two.rdf");
+        let stream = parse_stream(
+            Cursor::new(about.as_bytes()),
+            Syntax::RdfXml,
+            "http://www.example.com",
+        )
+        .chunk_size(0);
+
+        let events: Vec<_> = block_on(stream.collect());
+        assert_eq!(2, events.len());
+    }
+
+    #[test]
+    fn stream_errors() {
+        let stream = parse_stream(
+            Cursor::new(&b"<rdf:RDF"[..]),
+            Syntax::RdfXml,
+            "http://www.example.com",
+        );
+
+        let events: Vec<_> = block_on(stream.collect());
+        assert!(events.iter().any(|event| match event {
+            Err(Error::Parse(_)) => true,
+            _ => false,
+        }));
+    }
+}
@@ -0,0 +1,237 @@
+//! IRIs from common vocabularies, such as `vocab::rdf::TYPE`.
+//!
+//! Each vocabulary has its usual `PREFIX` and namespace `NS` as well as
+//! constants for its more frequently used terms. `PREFIXES` lists them
+//! all, and is used by `Namespaces::well_known`.
+
+macro_rules! vocabulary {
+    ($(#[$attr:meta])* $name:ident, $prefix:tt, $ns:tt, { $($constant:ident = $local:tt,)* }) => {
+        $(#[$attr])*
+        pub mod $name {
+            pub const PREFIX: &str = $prefix;
+            pub const NS: &str = $ns;
+            $(pub const $constant: &str = concat!($ns, $local);)*
+        }
+    };
+}
+
+vocabulary!(
+    /// The RDF vocabulary.
+    rdf, "rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#", {
+        TYPE = "type",
+        PROPERTY = "Property",
+        STATEMENT = "Statement",
+        SUBJECT = "subject",
+        PREDICATE = "predicate",
+        OBJECT = "object",
+        VALUE = "value",
+        LIST = "List",
+        FIRST = "first",
+        REST = "rest",
+        NIL = "nil",
+        LANG_STRING = "langString",
+        XML_LITERAL = "XMLLiteral",
+    }
+);
+
+vocabulary!(
+    /// RDF Schema.
+    rdfs, "rdfs", "http://www.w3.org/2000/01/rdf-schema#", {
+        RESOURCE = "Resource",
+        CLASS = "Class",
+        LITERAL = "Literal",
+        DATATYPE = "Datatype",
+        LABEL = "label",
+        COMMENT = "comment",
+        SUB_CLASS_OF = "subClassOf",
+        SUB_PROPERTY_OF = "subPropertyOf",
+        DOMAIN = "domain",
+        RANGE = "range",
+        SEE_ALSO = "seeAlso",
+        IS_DEFINED_BY = "isDefinedBy",
+        MEMBER = "member",
+    }
+);
+
+vocabulary!(
+    /// The Web Ontology Language.
+    owl, "owl", "http://www.w3.org/2002/07/owl#", {
+        ONTOLOGY = "Ontology",
+        IMPORTS = "imports",
+        VERSION_IRI = "versionIRI",
+        CLASS = "Class",
+        THING = "Thing",
+        NOTHING = "Nothing",
+        NAMED_INDIVIDUAL = "NamedIndividual",
+        OBJECT_PROPERTY = "ObjectProperty",
+        DATATYPE_PROPERTY = "DatatypeProperty",
+        ANNOTATION_PROPERTY = "AnnotationProperty",
+        RESTRICTION = "Restriction",
+        ON_PROPERTY = "onProperty",
+        SOME_VALUES_FROM = "someValuesFrom",
+        ALL_VALUES_FROM = "allValuesFrom",
+        EQUIVALENT_CLASS = "equivalentClass",
+        EQUIVALENT_PROPERTY = "equivalentProperty",
+        DISJOINT_WITH = "disjointWith",
+        INVERSE_OF = "inverseOf",
+        SAME_AS = "sameAs",
+        DEPRECATED = "deprecated",
+    }
+);
+
+vocabulary!(
+    /// XML Schema datatypes.
+    xsd, "xsd", "http://www.w3.org/2001/XMLSchema#", {
+        STRING = "string",
+        BOOLEAN = "boolean",
+        DECIMAL = "decimal",
+        INTEGER = "integer",
+        NON_NEGATIVE_INTEGER = "nonNegativeInteger",
+        INT = "int",
+        LONG = "long",
+        FLOAT = "float",
+        DOUBLE = "double",
+        DATE = "date",
+        DATE_TIME = "dateTime",
+        ANY_URI = "anyURI",
+    }
+);
+
+vocabulary!(
+    /// Simple Knowledge Organization System.
+    skos, "skos", "http://www.w3.org/2004/02/skos/core#", {
+        CONCEPT = "Concept",
+        CONCEPT_SCHEME = "ConceptScheme",
+        IN_SCHEME = "inScheme",
+        PREF_LABEL = "prefLabel",
+        ALT_LABEL = "altLabel",
+        HIDDEN_LABEL = "hiddenLabel",
+        DEFINITION = "definition",
+        NOTE = "note",
+        BROADER = "broader",
+        NARROWER = "narrower",
+        RELATED = "related",
+        EXACT_MATCH = "exactMatch",
+        CLOSE_MATCH = "closeMatch",
+    }
+);
+
+vocabulary!(
+    /// Dublin Core elements.
+    dc, "dc", "http://purl.org/dc/elements/1.1/", {
+        TITLE = "title",
+        CREATOR = "creator",
+        SUBJECT = "subject",
+        DESCRIPTION = "description",
+        PUBLISHER = "publisher",
+        CONTRIBUTOR = "contributor",
+        DATE = "date",
+        TYPE = "type",
+        FORMAT = "format",
+        IDENTIFIER = "identifier",
+        SOURCE = "source",
+        LANGUAGE = "language",
+        RIGHTS = "rights",
+    }
+);
+
+vocabulary!(
+    /// Dublin Core terms.
+    dcterms, "dcterms", "http://purl.org/dc/terms/", {
+        TITLE = "title",
+        CREATOR = "creator",
+        SUBJECT = "subject",
+        DESCRIPTION = "description",
+        PUBLISHER = "publisher",
+        CONTRIBUTOR = "contributor",
+        CREATED = "created",
+        MODIFIED = "modified",
+        IDENTIFIER = "identifier",
+        SOURCE = "source",
+        LICENSE = "license",
+    }
+);
+
+vocabulary!(
+    /// Friend of a Friend.
+    foaf, "foaf", "http://xmlns.com/foaf/0.1/", {
+        AGENT = "Agent",
+        PERSON = "Person",
+        ORGANIZATION = "Organization",
+        NAME = "name",
+        GIVEN_NAME = "givenName",
+        FAMILY_NAME = "familyName",
+        MBOX = "mbox",
+        HOMEPAGE = "homepage",
+        KNOWS = "knows",
+        DEPICTION = "depiction",
+    }
+);
+
+vocabulary!(
+    /// Schema.org.
+    schema, "schema", "http://schema.org/", {
+        THING = "Thing",
+        PERSON = "Person",
+        ORGANIZATION = "Organization",
+        NAME = "name",
+        DESCRIPTION = "description",
+        URL = "url",
+        IDENTIFIER = "identifier",
+        SAME_AS = "sameAs",
+    }
+);
+
+vocabulary!(
+    /// The PROV ontology.
+    prov, "prov", "http://www.w3.org/ns/prov#", {
+        ENTITY = "Entity",
+        ACTIVITY = "Activity",
+        AGENT = "Agent",
+        USED = "used",
+        WAS_GENERATED_BY = "wasGeneratedBy",
+        WAS_DERIVED_FROM = "wasDerivedFrom",
+        WAS_ATTRIBUTED_TO = "wasAttributedTo",
+        WAS_ASSOCIATED_WITH = "wasAssociatedWith",
+        GENERATED_AT_TIME = "generatedAtTime",
+    }
+);
+
+vocabulary!(
+    /// OBO Foundry ontologies, with the annotations and relations most
+    /// used across them.
+    obo, "obo", "http://purl.obolibrary.org/obo/", {
+        DEFINITION = "IAO_0000115",
+        EDITOR_NOTE = "IAO_0000116",
+        TERM_REPLACED_BY = "IAO_0100001",
+        PART_OF = "BFO_0000050",
+        HAS_PART = "BFO_0000051",
+    }
+);
+
+/// The prefix and namespace of every vocabulary here.
+pub const PREFIXES: &[(&str, &str)] = &[
+    (rdf::PREFIX, rdf::NS),
+    (rdfs::PREFIX, rdfs::NS),
+    (owl::PREFIX, owl::NS),
+    (xsd::PREFIX, xsd::NS),
+    (skos::PREFIX, skos::NS),
+    (dc::PREFIX, dc::NS),
+    (dcterms::PREFIX, dcterms::NS),
+    (foaf::PREFIX, foaf::NS),
+    (schema::PREFIX, schema::NS),
+    (prov::PREFIX, prov::NS),
+    (obo::PREFIX, obo::NS),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constants() {
+        assert_eq!("http://www.w3.org/1999/02/22-rdf-syntax-ns#type", rdf::TYPE);
+        assert_eq!("http://www.w3.org/2000/01/rdf-schema#label", rdfs::LABEL);
+        assert_eq!("http://purl.obolibrary.org/obo/BFO_0000050", obo::PART_OF);
+    }
+}
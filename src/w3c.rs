@@ -0,0 +1,466 @@
+//! Runner for the W3C RDF test suites.
+//!
+//! Each suite in `rdf-tests` comes with a `manifest.ttl` describing its
+//! tests; we parse it with our own Turtle parser, run every test it
+//! lists and report the outcome, optionally as EARL.
+use super::*;
+
+use graph::Graph;
+use push::MemoryParserHandler;
+use push::Parser;
+use vocab::rdf;
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+// The manifest vocabularies are only of use here, so are not in `vocab`.
+const MF: &str = "http://www.w3.org/2001/sw/DataAccess/tests/test-manifest#";
+const RDFT: &str = "http://www.w3.org/ns/rdftest#";
+
+const PROJECT: &str = "https://github.com/phillord/raptor-rs";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Suite {
+    RdfXml,
+    Turtle,
+    TriG,
+    NTriples,
+    NQuads,
+}
+
+impl Suite {
+    /// The directory of this suite within `rdf-tests`.
+    pub fn dir(&self) -> &'static str {
+        match self {
+            Suite::RdfXml => "rdf-xml",
+            Suite::Turtle => "turtle",
+            Suite::TriG => "trig",
+            Suite::NTriples => "n-triples",
+            Suite::NQuads => "n-quads",
+        }
+    }
+
+    /// The IRI the suite was published at, which the manifest and the
+    /// expected results are relative to.
+    pub fn base(&self) -> &'static str {
+        match self {
+            Suite::RdfXml => "http://www.w3.org/2013/RDFXMLTests/",
+            Suite::Turtle => "http://www.w3.org/2013/TurtleTests/",
+            Suite::TriG => "http://www.w3.org/2013/TriGTests/",
+            Suite::NTriples => "http://www.w3.org/2013/N-TriplesTests/",
+            Suite::NQuads => "http://www.w3.org/2013/N-QuadsTests/",
+        }
+    }
+
+    /// The libraptor parser under test.
+    pub fn parser(&self) -> &'static str {
+        match self {
+            Suite::RdfXml => "rdfxml",
+            Suite::Turtle => "turtle",
+            Suite::TriG => "trig",
+            Suite::NTriples => "ntriples",
+            Suite::NQuads => "nquads",
+        }
+    }
+
+    // The parser for expected results of eval tests.
+    fn result_parser(&self) -> &'static str {
+        match self {
+            Suite::TriG | Suite::NQuads => "nquads",
+            _ => "ntriples",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestType {
+    PositiveSyntax,
+    NegativeSyntax,
+    Eval,
+    NegativeEval,
+}
+
+#[derive(Clone, Debug)]
+pub struct TestCase {
+    pub id: IRI,
+    pub name: String,
+    pub test_type: TestType,
+    pub action: IRI,
+    pub result: Option<IRI>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Passed,
+    Failed(String),
+}
+
+#[derive(Debug)]
+pub struct Manifest {
+    suite: Suite,
+    dir: PathBuf,
+    tests: Vec<TestCase>,
+}
+
+fn iri(namespace: &str, local: &str) -> Term {
+    Term::URI(IRI(format!("{}{}", namespace, local)))
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn is_error(message: &LogMessage) -> bool {
+    match message.level {
+        LogLevel::Error | LogLevel::Fatal => true,
+        _ => false,
+    }
+}
+
+// Parse content into a graph, also returning the number of errors;
+// warnings are not counted.
+fn parse_to_graph(kind: &str, baseuri: &str, content: &str) -> (Graph, usize) {
+    let m = MemoryParserHandler::new();
+    {
+        let mut p = Parser::new(kind, baseuri, &m);
+        p.parse_chunk(content);
+        p.parse_complete();
+    }
+
+    let mut graph = Graph::new();
+    let mut errors = 0;
+    for event in m.0 {
+        match event {
+            Ok(statement) => {
+                graph.insert(statement);
+            }
+            Err(ref message) if is_error(message) => errors += 1,
+            Err(_) => {}
+        }
+    }
+    (graph, errors)
+}
+
+// The members of the RDF collection starting at `head`.
+fn list_items<'a>(graph: &'a Graph, head: &'a Term) -> Vec<&'a Term> {
+    let first = Term::URI(IRI::new(rdf::FIRST));
+    let rest = Term::URI(IRI::new(rdf::REST));
+    let nil = Term::URI(IRI::new(rdf::NIL));
+
+    let mut items = vec![];
+    let mut node = head;
+    while *node != nil {
+        match graph.objects(node, &first).next() {
+            Some(item) => items.push(item),
+            None => break,
+        }
+        match graph.objects(node, &rest).next() {
+            Some(next) => node = next,
+            None => break,
+        }
+    }
+    items
+}
+
+fn test_type_from_iri(type_iri: &str) -> Option<TestType> {
+    if !type_iri.starts_with(RDFT) {
+        return None;
+    }
+    // For example rdft:TestTurtleNegativeEval; the order matters as
+    // "NegativeEval" also ends with "Eval".
+    if type_iri.ends_with("PositiveSyntax") {
+        Some(TestType::PositiveSyntax)
+    } else if type_iri.ends_with("NegativeSyntax") {
+        Some(TestType::NegativeSyntax)
+    } else if type_iri.ends_with("NegativeEval") {
+        Some(TestType::NegativeEval)
+    } else if type_iri.ends_with("Eval") {
+        Some(TestType::Eval)
+    } else {
+        None
+    }
+}
+
+impl Manifest {
+    /// Read the manifest of `suite` from `root`, normally `rdf-tests`.
+    pub fn load(root: &Path, suite: Suite) -> io::Result<Manifest> {
+        let dir = root.join(suite.dir());
+        let content = fs::read_to_string(dir.join("manifest.ttl"))?;
+        let baseuri = format!("{}manifest.ttl", suite.base());
+
+        let (graph, errors) = parse_to_graph("turtle", &baseuri, &content);
+        if errors > 0 {
+            return Err(invalid_data(format!(
+                "{} errors parsing manifest for {}",
+                errors,
+                suite.dir()
+            )));
+        }
+
+        let rdf_type = Term::URI(IRI::new(rdf::TYPE));
+        let manifest_type = iri(MF, "Manifest");
+        let manifest = graph
+            .subjects(&rdf_type, &manifest_type)
+            .next()
+            .ok_or_else(|| invalid_data(format!("No mf:Manifest in {}", suite.dir())))?;
+
+        let mf_entries = iri(MF, "entries");
+        let mf_name = iri(MF, "name");
+        let mf_action = iri(MF, "action");
+        let mf_result = iri(MF, "result");
+        let approval = iri(RDFT, "approval");
+        let rejected = iri(RDFT, "Rejected");
+
+        let mut tests = vec![];
+        for head in graph.objects(manifest, &mf_entries) {
+            for entry in list_items(&graph, head) {
+                let id = match entry {
+                    Term::URI(id) => id.clone(),
+                    _ => continue,
+                };
+                if graph.objects(entry, &approval).any(|a| *a == rejected) {
+                    continue;
+                }
+
+                let test_type = graph
+                    .objects(entry, &rdf_type)
+                    .filter_map(|t| match t {
+                        Term::URI(t) => test_type_from_iri(t.as_str()),
+                        _ => None,
+                    })
+                    .next();
+                let action = graph.objects(entry, &mf_action).next();
+
+                let (test_type, action) = match (test_type, action) {
+                    (Some(test_type), Some(Term::URI(action))) => (test_type, action.clone()),
+                    _ => {
+                        return Err(invalid_data(format!(
+                            "Test {} has no recognised type or action",
+                            id.as_str()
+                        )))
+                    }
+                };
+
+                tests.push(TestCase {
+                    name: match graph.objects(entry, &mf_name).next() {
+                        Some(Term::Literal(l)) => l.value.clone(),
+                        _ => id.as_str().to_string(),
+                    },
+                    result: match graph.objects(entry, &mf_result).next() {
+                        Some(Term::URI(r)) => Some(r.clone()),
+                        _ => None,
+                    },
+                    id,
+                    test_type,
+                    action,
+                });
+            }
+        }
+
+        Ok(Manifest { suite, dir, tests })
+    }
+
+    pub fn tests(&self) -> &[TestCase] {
+        &self.tests
+    }
+
+    /// Run every test in the manifest.
+    pub fn run(&self) -> Vec<(TestCase, Outcome)> {
+        self.tests
+            .iter()
+            .map(|test| (test.clone(), self.run_test(test)))
+            .collect()
+    }
+
+    // Tests refer to their files by IRI under the suite base.
+    fn local_path(&self, iri: &IRI) -> Option<PathBuf> {
+        let base = self.suite.base();
+        if iri.as_str().starts_with(base) {
+            Some(self.dir.join(&iri.as_str()[base.len()..]))
+        } else {
+            None
+        }
+    }
+
+    // Parse the suite's copy of `iri`, resolving relative IRIs against
+    // `base`.
+    fn parse_file(&self, kind: &str, iri: &IRI, base: &IRI) -> Result<(Graph, usize), String> {
+        let path = self
+            .local_path(iri)
+            .ok_or_else(|| format!("{} is not part of the suite", iri.as_str()))?;
+
+        match fs::read_to_string(&path) {
+            Ok(content) => Ok(parse_to_graph(kind, base.as_str(), &content)),
+            // Every syntax we test must be UTF-8, so this is a
+            // syntax error in its own right.
+            Err(ref e) if e.kind() == io::ErrorKind::InvalidData => Ok((Graph::new(), 1)),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn run_test(&self, test: &TestCase) -> Outcome {
+        let (graph, errors) = match self.parse_file(self.suite.parser(), &test.action, &test.action)
+        {
+            Ok(parsed) => parsed,
+            Err(e) => return Outcome::Failed(e),
+        };
+
+        match test.test_type {
+            TestType::NegativeSyntax | TestType::NegativeEval => {
+                if errors > 0 {
+                    Outcome::Passed
+                } else {
+                    Outcome::Failed("parsed without error".to_string())
+                }
+            }
+            TestType::PositiveSyntax | TestType::Eval if errors > 0 => {
+                Outcome::Failed(format!("{} errors while parsing", errors))
+            }
+            TestType::PositiveSyntax => Outcome::Passed,
+            TestType::Eval => {
+                let result = match test.result {
+                    Some(ref result) => result,
+                    None => return Outcome::Failed("eval test without result".to_string()),
+                };
+                // Any relative IRIs in the expected results are
+                // resolved against the action, as the action's own are.
+                let expected =
+                    match self.parse_file(self.suite.result_parser(), result, &test.action) {
+                        Ok((expected, 0)) => expected,
+                        Ok((_, errors)) => {
+                            return Outcome::Failed(format!("{} errors in expected result", errors))
+                        }
+                        Err(e) => return Outcome::Failed(e),
+                    };
+
+                if graph.is_isomorphic(&expected) {
+                    Outcome::Passed
+                } else {
+                    Outcome::Failed(format!(
+                        "parsed {} statements, not isomorphic to the {} expected",
+                        graph.len(),
+                        expected.len()
+                    ))
+                }
+            }
+        }
+    }
+}
+
+/// An EARL report, in Turtle, of the outcome of running tests.
+pub fn earl_report(results: &[(TestCase, Outcome)]) -> String {
+    let mut report = String::new();
+    report.push_str("@prefix earl: <http://www.w3.org/ns/earl#> .\n");
+    report.push_str("@prefix doap: <http://usefulinc.com/ns/doap#> .\n\n");
+    report.push_str(&format!(
+        "<{}> a doap:Project, earl:TestSubject, earl:Software ;\n    doap:name \"raptor-rs\" .\n",
+        PROJECT
+    ));
+
+    for (test, outcome) in results {
+        let outcome = match outcome {
+            Outcome::Passed => "earl:passed",
+            Outcome::Failed(_) => "earl:failed",
+        };
+        report.push_str(&format!(
+            "\n[] a earl:Assertion ;\n    earl:assertedBy <{0}> ;\n    earl:subject <{0}> ;\n    earl:test <{1}> ;\n    earl:result [ a earl:TestResult ; earl:outcome {2} ] ;\n    earl:mode earl:automatic .\n",
+            PROJECT,
+            test.id.as_str(),
+            outcome
+        ));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_suite(suite: Suite) {
+        let root = Path::new("./").join("rdf-tests");
+        if !root.join(suite.dir()).is_dir() {
+            panic!("rdf-tests/{} not found", suite.dir());
+        }
+
+        let manifest = Manifest::load(&root, suite).unwrap();
+        assert_ne!(
+            manifest.tests().len(),
+            0,
+            "No test found in W3C test-suite, something must be wrong"
+        );
+
+        let failures: Vec<String> = manifest
+            .run()
+            .into_iter()
+            .filter_map(|(test, outcome)| match outcome {
+                Outcome::Failed(why) => Some(format!("{}: {}", test.name, why)),
+                Outcome::Passed => None,
+            })
+            .collect();
+
+        assert!(
+            failures.is_empty(),
+            format!(
+                "{} of {} failed:\n{}",
+                failures.len(),
+                manifest.tests().len(),
+                failures.join("\n")
+            )
+        );
+    }
+
+    #[test]
+    fn rdf_xml_suite() {
+        check_suite(Suite::RdfXml);
+    }
+
+    #[test]
+    fn turtle_suite() {
+        check_suite(Suite::Turtle);
+    }
+
+    #[test]
+    fn trig_suite() {
+        check_suite(Suite::TriG);
+    }
+
+    #[test]
+    fn ntriples_suite() {
+        check_suite(Suite::NTriples);
+    }
+
+    #[test]
+    fn nquads_suite() {
+        check_suite(Suite::NQuads);
+    }
+
+    #[test]
+    fn test_types() {
+        assert_eq!(
+            test_type_from_iri("http://www.w3.org/ns/rdftest#TestTurtleNegativeEval"),
+            Some(TestType::NegativeEval)
+        );
+        assert_eq!(
+            test_type_from_iri("http://www.w3.org/ns/rdftest#TestXMLEval"),
+            Some(TestType::Eval)
+        );
+        assert_eq!(test_type_from_iri("http://www.example.com/TestEval"), None);
+    }
+
+    #[test]
+    fn earl() {
+        let test = TestCase {
+            id: IRI::new("http://www.w3.org/2013/TurtleTests/manifest.ttl#IRI_subject"),
+            name: "IRI_subject".to_string(),
+            test_type: TestType::Eval,
+            action: IRI::new("http://www.w3.org/2013/TurtleTests/IRI_subject.ttl"),
+            result: None,
+        };
+        let report = earl_report(&[(test, Outcome::Passed)]);
+
+        assert!(report.contains("earl:test <http://www.w3.org/2013/TurtleTests/manifest.ttl#IRI_subject>"));
+        assert!(report.contains("earl:outcome earl:passed"));
+    }
+}